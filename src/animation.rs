@@ -1,66 +1,346 @@
-use bevy::prelude::*;
+use bevy::{
+    asset::{
+        AssetLoader, LoadContext, LoadDirectError, ParseAssetPathError, ReadAssetBytesError,
+        io::{AssetReaderError, Reader},
+    },
+    prelude::*,
+    sprite::Anchor,
+};
 use serde::{Deserialize, Serialize};
-use std::collections::BTreeMap;
+use std::{collections::BTreeMap, time::Duration};
 use thiserror::Error;
 
 use crate::game::InGameState;
 
+mod controller;
+mod crossfade;
+mod dragonbones;
+mod formats;
+
+pub(crate) use controller::{AnimationController, Condition};
+pub(crate) use crossfade::Crossfade;
+pub(crate) use dragonbones::{DragonBonesSkeleton, Skeleton};
+pub(crate) use formats::SpritesheetFormat;
+
+const RAW_LAYOUT_LABEL: &str = "layout";
+const SIDECAR_EXTENSION: &str = "animations.json";
+const DEFAULT_FPS: f32 = 24.0;
+/// Shortest a frame is shown, so playback always moves forward
+const MIN_FRAME_DURATION: Duration = Duration::from_millis(1);
+
+/// A single problem with a spritesheet, naming the sub-texture or animation at fault.
+#[derive(Error, Debug)]
+pub(crate) enum SpritesheetProblem {
+    #[error("texture {name} is not named <animation>_<index>")]
+    BadNameFormat { name: String },
+    #[error("texture {name} has a frame index that is not a number")]
+    NonNumericFrameIndex { name: String },
+    #[error("texture {name} repeats frame {position} of animation {animation}")]
+    DuplicateFrameIndex {
+        name: String,
+        animation: String,
+        position: usize,
+    },
+    #[error("animation {animation} is missing frame {missing} before texture {name}")]
+    FrameGap {
+        name: String,
+        animation: String,
+        missing: usize,
+    },
+    #[error("texture {name} at {rect:?} lies outside the {image_size} image")]
+    OutOfBounds {
+        name: String,
+        rect: URect,
+        image_size: UVec2,
+    },
+    #[error("texture {name} is rotated, disable rotation when exporting")]
+    RotatedFrame { name: String },
+    #[error("animation {animation} refers to unknown texture {name}")]
+    UnknownFrame { name: String, animation: String },
+    #[error("frame tag {animation} ({from}..={to}) is out of range")]
+    TagOutOfRange {
+        animation: String,
+        from: usize,
+        to: usize,
+    },
+    #[error("settings are given for missing animation {animation}")]
+    MissingAnimation { animation: String },
+    #[error("marker {marker} of animation {animation} is past its last frame")]
+    MarkerOutOfRange { animation: String, marker: String },
+    #[error("animation {animation} must have a positive, finite fps")]
+    InvalidFps { animation: String },
+}
+
+/// Every problem found in a spritesheet, so a broken export can be fixed in one go.
 #[derive(Error, Debug)]
-#[error("{0}")]
-pub(crate) struct SpritesheetParsingError(String);
+#[error("invalid spritesheet: {}", .0.iter().map(ToString::to_string).collect::<Vec<_>>().join("; "))]
+pub(crate) struct SpritesheetParsingError(pub(crate) Vec<SpritesheetProblem>);
+
+impl From<SpritesheetProblem> for SpritesheetParsingError {
+    fn from(problem: SpritesheetProblem) -> Self {
+        SpritesheetParsingError(vec![problem])
+    }
+}
+
+#[derive(Error, Debug)]
+pub(crate) enum SpritesheetLoaderError {
+    #[error("could not read spritesheet: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("could not parse spritesheet json: {0}")]
+    Json(#[from] serde_json::Error),
+    #[error("invalid spritesheet image path: {0}")]
+    ImagePath(#[from] ParseAssetPathError),
+    #[error("could not read animation settings: {0}")]
+    Sidecar(#[from] ReadAssetBytesError),
+    #[error("could not load spritesheet image to check its size: {0}")]
+    Image(#[from] Box<LoadDirectError>),
+    #[error(transparent)]
+    Parsing(#[from] SpritesheetParsingError),
+}
 
 pub(crate) struct AnimationPlugin;
 
 impl Plugin for AnimationPlugin {
     fn build(&self, app: &mut App) {
-        app.init_asset::<AnimationTextureAtlasLayout>()
-            .add_systems(Update, animate.run_if(in_state(InGameState::Running)));
+        app.add_plugins(dragonbones::DragonBonesPlugin)
+            .init_asset::<AnimationTextureAtlasLayout>()
+            .init_asset_loader::<AnimationTextureAtlasLayoutLoader>()
+            .add_systems(Update, attach_layout.before(AnimationSystems))
+            .add_systems(
+                Update,
+                (
+                    controller::update_controllers::<AnimatedSprite>,
+                    crossfade::start_crossfades,
+                    animate,
+                    crossfade::update_crossfades,
+                )
+                    .chain()
+                    .in_set(AnimationSystems)
+                    .run_if(in_state(InGameState::Running)),
+            )
+            .add_systems(PostUpdate, apply_frame_anchor);
+    }
+}
+
+/// Systems that advance animations, gameplay systems that change clips or controller
+/// parameters should run before them.
+#[derive(SystemSet, Debug, Clone, PartialEq, Eq, Hash)]
+pub(crate) struct AnimationSystems;
+
+/// How a clip continues once it reaches its last frame.
+#[derive(Serialize, Deserialize, Default, Clone, Copy, PartialEq, Eq, Debug)]
+#[serde(rename_all = "snake_case")]
+pub(crate) enum PlaybackMode {
+    #[default]
+    Loop,
+    /// Plays once and holds the last frame
+    Once,
+    /// Plays once and then resumes the animation that was playing before it
+    OnceThenPrevious,
+    /// Plays forwards then backwards, repeatedly
+    PingPong,
+    /// Loops from the last frame to the first
+    Reverse,
+}
+
+/// Playback settings for a clip, unset fields fall back to what the spritesheet format
+/// provides (e.g. per-frame durations and tag directions from Aseprite) and then to defaults.
+#[derive(Serialize, Deserialize, Default, Clone, Debug)]
+#[serde(default)]
+pub(crate) struct ClipSettings {
+    fps: Option<f32>,
+    mode: Option<PlaybackMode>,
+    /// Frame positions within the clip keyed by marker name, e.g. `"footstep": [5, 13]`
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    markers: BTreeMap<String, Vec<usize>>,
+}
+
+/// Triggered when a clip that doesn't repeat plays its last frame.
+#[derive(EntityEvent, Debug, Clone)]
+pub(crate) struct AnimationFinished {
+    pub(crate) entity: Entity,
+    #[allow(unused)]
+    pub(crate) name: String,
+}
+
+/// Triggered when a clip reaches a frame tagged with a marker in its [`ClipSettings`].
+#[derive(EntityEvent, Debug, Clone)]
+pub(crate) struct AnimationMarker {
+    pub(crate) entity: Entity,
+    #[allow(unused)]
+    pub(crate) animation: String,
+    #[allow(unused)]
+    pub(crate) marker: String,
+}
+
+#[derive(Clone)]
+pub(crate) struct AnimationClip {
+    frames: Vec<usize>,
+    /// How long each frame is shown, parallel to `frames`
+    durations: Vec<Duration>,
+    mode: PlaybackMode,
+    markers: BTreeMap<String, Vec<usize>>,
+}
+
+impl AnimationClip {
+    fn start(&self) -> usize {
+        match self.mode {
+            PlaybackMode::Reverse => self.frames.len().saturating_sub(1),
+            _ => 0,
+        }
+    }
+
+    fn markers_at(&self, frame: usize) -> impl Iterator<Item = &str> {
+        self.markers
+            .iter()
+            .filter(move |(_, frames)| frames.contains(&frame))
+            .map(|(name, _)| name.as_str())
     }
 }
 
 #[derive(Component)]
 #[require(Sprite)]
 pub(crate) struct AnimatedSprite {
-    pub(crate) animation_name: String,
+    animation_name: String,
+    previous_animation: Option<String>,
     layout: Handle<AnimationTextureAtlasLayout>,
-    timer: Timer,
+    /// Time spent on the current frame
+    elapsed: Duration,
+    /// Position within the current clip, `None` until the clip has been started
+    frame: Option<usize>,
+    backwards: bool,
+    finished: bool,
+    /// Playback rate multiplier, on top of the virtual clock so slow motion applies too
+    speed: f32,
 }
 
 impl AnimatedSprite {
+    /// The sprite's image and atlas are filled in by [`attach_layout`] once the layout has
+    /// finished loading.
     pub(crate) fn new(
-        image: Handle<Image>,
-        layout_handle: Handle<AnimationTextureAtlasLayout>,
-        layout: &AnimationTextureAtlasLayout,
+        layout: Handle<AnimationTextureAtlasLayout>,
         animation: &str,
-        fps: u8,
     ) -> impl Bundle + use<> {
-        (
-            Sprite::from_atlas_image(
-                image,
-                TextureAtlas {
-                    layout: layout.raw_layout.clone(),
-                    index: layout.get_first(animation).unwrap_or(0),
-                },
-            ),
-            AnimatedSprite {
-                layout: layout_handle,
-                animation_name: animation.to_string(),
-                timer: Timer::from_seconds(1.0 / fps as f32, TimerMode::Repeating),
-            },
-        )
+        AnimatedSprite {
+            layout,
+            animation_name: animation.to_string(),
+            previous_animation: None,
+            elapsed: Duration::ZERO,
+            frame: None,
+            backwards: false,
+            finished: false,
+            speed: 1.0,
+        }
+    }
+
+    /// Switches to `animation`, restarting it from its first frame. Does nothing if the
+    /// animation is already playing.
+    pub(crate) fn play(&mut self, animation: &str) {
+        if self.animation_name == animation {
+            return;
+        }
+
+        let previous = std::mem::replace(&mut self.animation_name, animation.to_string());
+        self.previous_animation = Some(previous);
+        self.restart();
+    }
+
+    pub(crate) fn restart(&mut self) {
+        self.frame = None;
+        self.backwards = false;
+        self.finished = false;
+        self.elapsed = Duration::ZERO;
+    }
+
+    /// Sets the playback rate multiplier, negative values are treated as paused.
+    pub(crate) fn set_speed(&mut self, speed: f32) {
+        self.speed = speed.max(0.0);
+    }
+
+    #[allow(unused)]
+    pub(crate) fn speed(&self) -> f32 {
+        self.speed
+    }
+
+    #[allow(unused)]
+    pub(crate) fn animation_name(&self) -> &str {
+        &self.animation_name
+    }
+
+    /// Whether a clip that doesn't repeat has reached its end.
+    pub(crate) fn is_finished(&self) -> bool {
+        self.finished
+    }
+
+    /// Moves one frame along `clip`, returns `false` if the clip has ended instead.
+    fn step(&mut self, clip: &AnimationClip) -> bool {
+        let len = clip.frames.len();
+        let frame = self.frame.unwrap_or_else(|| clip.start());
+
+        let next = match clip.mode {
+            PlaybackMode::Loop => (frame + 1) % len,
+            PlaybackMode::Reverse => (frame + len - 1) % len,
+            PlaybackMode::Once | PlaybackMode::OnceThenPrevious => {
+                if frame + 1 >= len {
+                    return false;
+                }
+                frame + 1
+            }
+            PlaybackMode::PingPong if len < 2 => frame,
+            PlaybackMode::PingPong => {
+                if self.backwards && frame == 0 || !self.backwards && frame + 1 == len {
+                    self.backwards = !self.backwards;
+                }
+
+                if self.backwards { frame - 1 } else { frame + 1 }
+            }
+        };
+
+        self.frame = Some(next);
+        true
+    }
+
+    fn finish(&mut self, clip: &AnimationClip) {
+        if clip.mode == PlaybackMode::OnceThenPrevious
+            && let Some(previous) = self.previous_animation.take()
+        {
+            self.animation_name = previous;
+            self.restart();
+            return;
+        }
+
+        self.finished = true;
     }
 }
 
-fn animate(
-    mut query: Query<(&mut AnimatedSprite, &mut Sprite)>,
-    time: Res<Time>,
+fn attach_layout(
+    mut query: Query<(&AnimatedSprite, &mut Sprite)>,
     layouts: Res<Assets<AnimationTextureAtlasLayout>>,
 ) {
-    for (mut animation, mut sprite) in query.iter_mut() {
-        if !animation.timer.tick(time.delta()).just_finished() {
+    for (animation, mut sprite) in query.iter_mut() {
+        if sprite.texture_atlas.is_some() {
+            continue;
+        }
+
+        let Some(layout) = layouts.get(&animation.layout) else {
             continue;
         };
 
+        sprite.image = layout.image.clone();
+        sprite.texture_atlas = Some(TextureAtlas {
+            layout: layout.raw_layout.clone(),
+            index: layout.get_first(&animation.animation_name).unwrap_or(0),
+        });
+    }
+}
+
+fn animate(
+    mut commands: Commands,
+    mut query: Query<(&mut AnimatedSprite, &mut Sprite, Entity)>,
+    time: Res<Time>,
+    layouts: Res<Assets<AnimationTextureAtlasLayout>>,
+) {
+    for (mut animation, mut sprite, entity) in query.iter_mut() {
         let Some(layout) = layouts.get(&animation.layout) else {
             continue;
         };
@@ -69,125 +349,477 @@ fn animate(
             continue;
         };
 
-        let Some(next_idx) = layout.cycle_next_multi(
-            &animation.animation_name,
-            atlas.index,
-            animation.timer.times_finished_this_tick() as usize,
-        ) else {
+        let Some(clip) = layout.clip(&animation.animation_name) else {
+            continue;
+        };
+
+        // Hot reloading can shorten the clip under a playing sprite, which then starts it over
+        let Some(mut frame) = animation.frame.filter(|&frame| frame < clip.frames.len()) else {
+            let start = clip.start();
+            animation.frame = Some(start);
+            animation.elapsed = Duration::ZERO;
+            atlas.index = clip.frames[start];
+            trigger_markers(
+                &mut commands,
+                entity,
+                &animation.animation_name,
+                clip,
+                start,
+            );
             continue;
         };
 
-        atlas.index = next_idx;
+        if animation.finished {
+            continue;
+        }
+
+        let delta = time.delta().mul_f32(animation.speed);
+        animation.elapsed += delta;
+        while animation.elapsed >= clip.durations[frame] {
+            animation.elapsed -= clip.durations[frame];
+            if !animation.step(clip) {
+                commands.trigger(AnimationFinished {
+                    entity,
+                    name: animation.animation_name.clone(),
+                });
+                animation.finish(clip);
+                break;
+            }
+
+            frame = animation.frame.unwrap_or(frame);
+            trigger_markers(
+                &mut commands,
+                entity,
+                &animation.animation_name,
+                clip,
+                frame,
+            );
+        }
+
+        // Finishing may have switched back to the previous clip, which is started next frame
+        if let Some(frame) = animation.frame {
+            atlas.index = clip.frames[frame];
+        }
     }
 }
 
-#[derive(Asset, TypePath, Clone)]
-pub(crate) struct AnimationTextureAtlasLayout {
-    raw_layout: Handle<TextureAtlasLayout>,
-    indices: BTreeMap<String, Vec<usize>>,
+/// Drives the playback rate of an entity's [`AnimatedSprite`] from one of its components,
+/// e.g. to match foot cadence to ground speed. Only applies while `animation` is playing,
+/// other clips play at normal speed.
+#[derive(Component)]
+pub(crate) struct PlaybackSpeedBinding<C: Component> {
+    animation: &'static str,
+    speed: fn(&C) -> f32,
 }
 
-impl AnimationTextureAtlasLayout {
-    pub(crate) fn from_json(
-        json: &str,
-        assets: &mut Assets<TextureAtlasLayout>,
-    ) -> Result<Self, BevyError> {
-        let mut raw = TextureAtlasLayout::new_empty(UVec2::ZERO);
-        let spritesheet_data: SpritesheetData = serde_json::from_str(json)?;
+impl<C: Component> PlaybackSpeedBinding<C> {
+    pub(crate) fn new(animation: &'static str, speed: fn(&C) -> f32) -> Self {
+        PlaybackSpeedBinding { animation, speed }
+    }
+}
 
-        let mut temp_indices_map: BTreeMap<String, Vec<(usize, usize)>> = BTreeMap::new();
+/// Applies [`PlaybackSpeedBinding`]s for `C`, should run after whatever updates `C` and
+/// before [`AnimationSystems`].
+pub(crate) fn bind_playback_speed<C: Component>(
+    mut query: Query<(&mut AnimatedSprite, &C, &PlaybackSpeedBinding<C>)>,
+) {
+    for (mut animation, component, binding) in query.iter_mut() {
+        let speed = if animation.animation_name == binding.animation {
+            (binding.speed)(component)
+        } else {
+            1.0
+        };
 
-        for texture in spritesheet_data.sub_texture {
-            let idx = raw.add_texture(URect::from_corners(
-                UVec2::new(texture.x as u32, texture.y as u32),
-                UVec2::new(
-                    (texture.x + texture.width) as u32,
-                    (texture.y + texture.height) as u32,
-                ),
-            ));
-            let mut split = texture.name.split("_");
-            let animation = split.next().ok_or(SpritesheetParsingError(
-                "Texture name format incorrect".to_string(),
-            ))?;
-            let position: usize = split
-                .next()
-                .ok_or("Texture name format incorrect".to_string())?
-                .parse()?;
+        if animation.speed != speed.max(0.0) {
+            animation.set_speed(speed);
+        }
+    }
+}
+
+fn trigger_markers(
+    commands: &mut Commands,
+    entity: Entity,
+    animation: &str,
+    clip: &AnimationClip,
+    frame: usize,
+) {
+    for marker in clip.markers_at(frame) {
+        commands.trigger(AnimationMarker {
+            entity,
+            animation: animation.to_string(),
+            marker: marker.to_string(),
+        });
+    }
+}
+
+/// Offsets trimmed frames so that every frame is positioned as if it were drawn on the
+/// untrimmed canvas, mirroring the offset when the sprite is flipped.
+fn apply_frame_anchor(
+    mut query: Query<(&AnimatedSprite, &Sprite, &mut Anchor)>,
+    layouts: Res<Assets<AnimationTextureAtlasLayout>>,
+) {
+    for (animation, sprite, mut anchor) in query.iter_mut() {
+        let Some(layout) = layouts.get(&animation.layout) else {
+            continue;
+        };
 
-            temp_indices_map
-                .entry(animation.to_string())
-                .and_modify(|e| e.push((position, idx)))
-                .or_insert(vec![]);
+        if let Some(frame_anchor) = layout.sprite_anchor(sprite) {
+            anchor.set_if_neq(frame_anchor);
         }
+    }
+}
+
+#[derive(Serialize, Deserialize, Default)]
+pub(crate) struct SpritesheetSettings {
+    /// Overrides format detection
+    format: Option<SpritesheetFormat>,
+}
+
+/// Loads [`AnimationTextureAtlasLayout`]s from spritesheet JSON files in any
+/// [`SpritesheetFormat`].
+///
+/// The raw [`TextureAtlasLayout`] is registered as the `layout` sub-asset and the image is
+/// loaded from the path given in the spritesheet, relative to the JSON file.
+#[derive(Default)]
+pub(crate) struct AnimationTextureAtlasLayoutLoader;
+
+impl AssetLoader for AnimationTextureAtlasLayoutLoader {
+    type Asset = AnimationTextureAtlasLayout;
+    type Settings = SpritesheetSettings;
+    type Error = SpritesheetLoaderError;
+
+    async fn load(
+        &self,
+        reader: &mut dyn Reader,
+        settings: &SpritesheetSettings,
+        load_context: &mut LoadContext<'_>,
+    ) -> Result<Self::Asset, Self::Error> {
+        let mut bytes = Vec::new();
+        reader.read_to_end(&mut bytes).await?;
+        let json: serde_json::Value = serde_json::from_slice(&bytes)?;
+        let format = settings
+            .format
+            .unwrap_or_else(|| SpritesheetFormat::detect(load_context.path(), &json));
+        let mut sheet = format.import(json)?;
+        let mut problems = std::mem::take(&mut sheet.problems);
+
+        let image_path = load_context.asset_path().resolve_embed(&sheet.image_path)?;
+        // Without a size in the spritesheet, the image is loaded right away to check against
+        let image_size = match sheet.image_size {
+            Some(size) => size,
+            None => load_context
+                .loader()
+                .immediate()
+                .load::<Image>(image_path.clone())
+                .await
+                .map_err(Box::new)?
+                .get()
+                .size(),
+        };
 
-        let mut indices = BTreeMap::new();
-        for (name, mut list) in temp_indices_map.into_iter() {
-            list.sort_by(|a, b| a.0.cmp(&b.0));
-            indices.insert(name, list.into_iter().map(|e| e.1).collect());
+        for frame in &sheet.frames {
+            if frame.rect.max.cmpgt(image_size).any() {
+                problems.push(SpritesheetProblem::OutOfBounds {
+                    name: frame.name.clone(),
+                    rect: frame.rect,
+                    image_size,
+                });
+            }
         }
 
-        let handle = assets.add(raw);
+        let image = load_context.load(image_path);
+
+        let mut settings = std::mem::take(&mut sheet.settings);
+        settings.extend(read_sidecar(load_context).await?);
+
+        let mut raw = TextureAtlasLayout::new_empty(UVec2::ZERO);
+        for frame in &sheet.frames {
+            raw.add_texture(frame.rect);
+        }
+        let raw_layout = load_context.add_labeled_asset(RAW_LAYOUT_LABEL.to_string(), raw);
+        let frame_anchors = sheet.frames.iter().map(|frame| frame.anchor).collect();
+
+        let mut animations = BTreeMap::new();
+        for (name, animation) in sheet.animations {
+            let clip_settings = settings.remove(&name).unwrap_or_default();
+            for (marker, frames) in &clip_settings.markers {
+                if frames.iter().any(|&frame| frame >= animation.frames.len()) {
+                    problems.push(SpritesheetProblem::MarkerOutOfRange {
+                        animation: name.clone(),
+                        marker: marker.clone(),
+                    });
+                }
+            }
+
+            let fps = clip_settings.fps.unwrap_or(DEFAULT_FPS);
+            if !fps.is_finite() || fps <= 0.0 {
+                problems.push(SpritesheetProblem::InvalidFps { animation: name });
+                continue;
+            }
+
+            let durations = animation
+                .frames
+                .iter()
+                .map(|&frame| {
+                    match (clip_settings.fps, sheet.frames[frame].duration) {
+                        (None, Some(duration)) if !duration.is_zero() => duration,
+                        _ => Duration::from_secs_f32(1.0 / fps),
+                    }
+                    .max(MIN_FRAME_DURATION)
+                })
+                .collect();
+
+            animations.insert(
+                name,
+                AnimationClip {
+                    frames: animation.frames,
+                    durations,
+                    mode: clip_settings.mode.or(animation.mode).unwrap_or_default(),
+                    markers: clip_settings.markers,
+                },
+            );
+        }
+
+        problems.extend(
+            settings
+                .into_keys()
+                .map(|animation| SpritesheetProblem::MissingAnimation { animation }),
+        );
+        if !problems.is_empty() {
+            return Err(SpritesheetParsingError(problems).into());
+        }
 
         Ok(AnimationTextureAtlasLayout {
-            raw_layout: handle,
-            indices,
+            image,
+            raw_layout,
+            animations,
+            frame_anchors,
         })
     }
 
-    pub(crate) fn get_first(&self, name: &str) -> Option<usize> {
-        self.indices.get(name).and_then(|v| v.first().copied())
+    fn extensions(&self) -> &[&str] {
+        &["json"]
     }
+}
 
-    #[allow(unused)]
-    pub(crate) fn cycle_next(&self, name: &str, current: usize) -> Option<usize> {
-        self.cycle_next_multi(name, current, 1)
+/// Reads per-animation settings from an optional `<name>.animations.json` file next to the
+/// spritesheet, so they survive the spritesheet being re-exported.
+async fn read_sidecar(
+    load_context: &mut LoadContext<'_>,
+) -> Result<BTreeMap<String, ClipSettings>, SpritesheetLoaderError> {
+    let Some(stem) = load_context.path().file_stem() else {
+        return Ok(BTreeMap::new());
+    };
+
+    let sidecar_path = load_context
+        .asset_path()
+        .resolve_embed(&format!("{}.{SIDECAR_EXTENSION}", stem.to_string_lossy()))?;
+
+    match load_context.read_asset_bytes(sidecar_path).await {
+        Ok(bytes) => Ok(serde_json::from_slice(&bytes)?),
+        Err(ReadAssetBytesError::AssetReaderError(AssetReaderError::NotFound(_))) => {
+            Ok(BTreeMap::new())
+        }
+        Err(err) => Err(err.into()),
     }
+}
 
-    pub(crate) fn cycle_next_multi(
-        &self,
-        name: &str,
-        current: usize,
-        count: usize,
-    ) -> Option<usize> {
-        let indices = self.indices.get(name)?;
-
-        let Some(cur_idx) = indices.iter().position(|&e| e == current) else {
-            return indices.first().copied();
-        };
+#[derive(Asset, TypePath, Clone)]
+pub(crate) struct AnimationTextureAtlasLayout {
+    #[dependency]
+    image: Handle<Image>,
+    raw_layout: Handle<TextureAtlasLayout>,
+    animations: BTreeMap<String, AnimationClip>,
+    /// Anchor of each atlas texture that places the center of the untrimmed frame at the
+    /// sprite's origin.
+    frame_anchors: Vec<Vec2>,
+}
+
+impl AnimationTextureAtlasLayout {
+    pub(crate) fn frame_anchor(&self, index: usize) -> Option<Vec2> {
+        self.frame_anchors.get(index).copied()
+    }
+
+    /// Anchor for the atlas frame `sprite` shows, mirrored to match its flip.
+    pub(crate) fn sprite_anchor(&self, sprite: &Sprite) -> Option<Anchor> {
+        let mut anchor = self.frame_anchor(sprite.texture_atlas.as_ref()?.index)?;
+
+        if sprite.flip_x {
+            anchor.x = -anchor.x;
+        }
+
+        if sprite.flip_y {
+            anchor.y = -anchor.y;
+        }
+
+        Some(Anchor(anchor))
+    }
 
-        Some(indices[(cur_idx + count) % indices.len()])
+    pub(crate) fn clip(&self, name: &str) -> Option<&AnimationClip> {
+        self.animations
+            .get(name)
+            .filter(|clip| !clip.frames.is_empty())
+    }
+
+    /// The atlas index a clip starts playing from.
+    pub(crate) fn get_first(&self, name: &str) -> Option<usize> {
+        self.clip(name).map(|clip| clip.frames[clip.start()])
     }
 
     #[allow(unused)]
     pub(crate) fn get_nth(&self, name: &str, n: usize) -> Option<usize> {
-        self.indices.get(name)?.get(n).copied()
+        self.animations.get(name)?.frames.get(n).copied()
     }
 
     #[allow(unused)]
     pub(crate) fn has_animation(&self, name: &str) -> bool {
-        self.indices.contains_key(name)
-    }
-}
-
-#[derive(Serialize, Deserialize)]
-#[serde(rename_all = "camelCase")]
-struct SpritesheetData {
-    image_path: String,
-    #[serde(rename = "SubTexture")]
-    sub_texture: Vec<SubTexture>,
-    name: String,
-}
-
-#[derive(Serialize, Deserialize)]
-#[serde(rename_all = "camelCase")]
-struct SubTexture {
-    frame_width: i64,
-    y: i64,
-    frame_height: i64,
-    width: i64,
-    frame_x: i64,
-    height: i64,
-    name: String,
-    frame_y: i64,
-    x: i64,
+        self.animations.contains_key(name)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::{
+        asset::LoadState,
+        image::{CompressedImageFormats, ImageLoader},
+    };
+
+    use super::*;
+
+    /// An app loading assets from `tests/assets` with everything spritesheets need.
+    pub(super) fn app() -> App {
+        let mut app = App::new();
+        app.add_plugins((
+            MinimalPlugins,
+            AssetPlugin {
+                file_path: "tests/assets".to_string(),
+                ..default()
+            },
+            ImagePlugin::default(),
+        ))
+        .register_asset_loader(ImageLoader::new(CompressedImageFormats::NONE))
+        .init_asset::<TextureAtlasLayout>()
+        .init_asset::<AnimationTextureAtlasLayout>()
+        .init_asset_loader::<AnimationTextureAtlasLayoutLoader>();
+        app
+    }
+
+    /// Updates the app until the asset loads, returning the error if it fails to.
+    pub(super) fn wait_for<A: Asset>(app: &mut App, handle: &Handle<A>) -> Result<(), String> {
+        for _ in 0..1000 {
+            app.update();
+            match app.world().resource::<AssetServer>().load_state(handle) {
+                LoadState::Loaded => return Ok(()),
+                LoadState::Failed(e) => return Err(e.to_string()),
+                _ => std::thread::sleep(Duration::from_millis(1)),
+            }
+        }
+
+        panic!("asset never finished loading");
+    }
+
+    fn load(path: &str) -> (App, Result<(), String>) {
+        let mut app = app();
+        let handle: Handle<AnimationTextureAtlasLayout> =
+            app.world().resource::<AssetServer>().load(path.to_string());
+        let result = wait_for(&mut app, &handle);
+        (app, result)
+    }
+
+    #[test]
+    fn loads_sheet_without_image_size() {
+        let (app, result) = load("spritesheets/strip.json");
+        result.unwrap();
+
+        let layouts = app
+            .world()
+            .resource::<Assets<AnimationTextureAtlasLayout>>();
+        let (_, layout) = layouts.iter().next().unwrap();
+        assert_eq!(layout.clip("walk").unwrap().frames, [0, 1]);
+    }
+
+    #[test]
+    fn checks_frames_against_the_image_when_the_sheet_has_no_size() {
+        let (_, result) = load("spritesheets/overflowing_strip.json");
+        let error = result.unwrap_err();
+        assert!(error.contains("texture walk_1"), "{error}");
+        assert!(error.contains("outside the [16, 8] image"), "{error}");
+    }
+
+    #[test]
+    fn restarts_clips_that_shrank_while_playing() {
+        let mut app = app();
+        let clip = AnimationClip {
+            frames: vec![4, 5],
+            durations: vec![Duration::from_millis(100); 2],
+            mode: PlaybackMode::Loop,
+            markers: BTreeMap::new(),
+        };
+        let layout = app
+            .world_mut()
+            .resource_mut::<Assets<AnimationTextureAtlasLayout>>()
+            .add(AnimationTextureAtlasLayout {
+                image: Handle::default(),
+                raw_layout: Handle::default(),
+                animations: BTreeMap::from([("walk".to_string(), clip)]),
+                frame_anchors: Vec::new(),
+            });
+
+        let animation = AnimatedSprite {
+            layout: layout.clone(),
+            animation_name: "walk".to_string(),
+            previous_animation: None,
+            elapsed: Duration::ZERO,
+            frame: Some(7),
+            backwards: false,
+            finished: false,
+            speed: 1.0,
+        };
+        let sprite = app
+            .world_mut()
+            .spawn((
+                animation,
+                Sprite::from_atlas_image(
+                    Handle::default(),
+                    TextureAtlas {
+                        layout: Handle::default(),
+                        index: 11,
+                    },
+                ),
+            ))
+            .id();
+
+        app.add_systems(Update, animate);
+        app.update();
+
+        let world = app.world();
+        assert_eq!(world.get::<AnimatedSprite>(sprite).unwrap().frame, Some(0));
+        let atlas = world.get::<Sprite>(sprite).unwrap().texture_atlas.clone();
+        assert_eq!(atlas.unwrap().index, 4);
+    }
+
+    #[test]
+    fn rejects_infinite_fps() {
+        let (_, result) = load("spritesheets/infinite_fps_strip.json");
+        let error = result.unwrap_err();
+        assert!(
+            error.contains("animation walk must have a positive"),
+            "{error}"
+        );
+    }
+
+    #[test]
+    fn keeps_frames_of_fast_clips_on_screen() {
+        let (app, result) = load("spritesheets/fast_strip.json");
+        result.unwrap();
+
+        let layouts = app
+            .world()
+            .resource::<Assets<AnimationTextureAtlasLayout>>();
+        let (_, layout) = layouts.iter().next().unwrap();
+        assert_eq!(
+            layout.clip("walk").unwrap().durations,
+            [MIN_FRAME_DURATION; 2]
+        );
+    }
 }
@@ -0,0 +1,160 @@
+use bevy::{
+    ecs::component::Mutable,
+    platform::collections::{HashMap, HashSet},
+    prelude::*,
+};
+
+use super::AnimatedSprite;
+
+/// A condition that has to hold for a [`Transition`] to be taken.
+#[derive(Clone, Copy, Debug)]
+#[allow(unused)]
+pub(crate) enum Condition {
+    /// The float parameter is greater than the value
+    Above(&'static str, f32),
+    /// The float parameter is less than the value
+    Below(&'static str, f32),
+    /// The bool parameter equals the value
+    Is(&'static str, bool),
+    /// The trigger was set since the controller was last evaluated
+    Triggered(&'static str),
+    /// The current clip doesn't repeat and has played its last frame
+    Finished,
+}
+
+#[derive(Clone, Debug)]
+struct Transition {
+    /// `None` allows the transition from every other state
+    from: Option<&'static str>,
+    to: &'static str,
+    conditions: Vec<Condition>,
+}
+
+/// An animation player an [`AnimationController`] can drive, such as an [`AnimatedSprite`] or a
+/// [`Skeleton`](super::Skeleton).
+pub(crate) trait Playback: Component<Mutability = Mutable> {
+    /// Switches to the clip called `name` from its start.
+    fn play(&mut self, name: &str);
+
+    /// Whether a clip that doesn't repeat has reached its end.
+    fn is_finished(&self) -> bool;
+}
+
+impl Playback for AnimatedSprite {
+    fn play(&mut self, name: &str) {
+        AnimatedSprite::play(self, name);
+    }
+
+    fn is_finished(&self) -> bool {
+        AnimatedSprite::is_finished(self)
+    }
+}
+
+/// Drives the entity's [`Playback`] component from gameplay parameters.
+///
+/// Every state plays the clip of the same name. Transitions are checked in the order they
+/// were added and the first one whose conditions all hold is taken, restarting the clip.
+#[derive(Component, Clone, Debug)]
+pub(crate) struct AnimationController {
+    state: &'static str,
+    transitions: Vec<Transition>,
+    floats: HashMap<&'static str, f32>,
+    bools: HashMap<&'static str, bool>,
+    triggers: HashSet<&'static str>,
+}
+
+impl AnimationController {
+    pub(crate) fn new(initial: &'static str) -> Self {
+        AnimationController {
+            state: initial,
+            transitions: Vec::new(),
+            floats: HashMap::default(),
+            bools: HashMap::default(),
+            triggers: HashSet::default(),
+        }
+    }
+
+    pub(crate) fn with_transition(
+        mut self,
+        from: &'static str,
+        to: &'static str,
+        conditions: impl IntoIterator<Item = Condition>,
+    ) -> Self {
+        self.transitions.push(Transition {
+            from: Some(from),
+            to,
+            conditions: conditions.into_iter().collect(),
+        });
+        self
+    }
+
+    #[allow(unused)]
+    pub(crate) fn with_transition_from_any(
+        mut self,
+        to: &'static str,
+        conditions: impl IntoIterator<Item = Condition>,
+    ) -> Self {
+        self.transitions.push(Transition {
+            from: None,
+            to,
+            conditions: conditions.into_iter().collect(),
+        });
+        self
+    }
+
+    pub(crate) fn set_float(&mut self, name: &'static str, value: f32) {
+        self.floats.insert(name, value);
+    }
+
+    pub(crate) fn set_bool(&mut self, name: &'static str, value: bool) {
+        self.bools.insert(name, value);
+    }
+
+    #[allow(unused)]
+    pub(crate) fn trigger(&mut self, name: &'static str) {
+        self.triggers.insert(name);
+    }
+
+    #[allow(unused)]
+    pub(crate) fn state(&self) -> &'static str {
+        self.state
+    }
+
+    fn holds(&self, condition: Condition, animation: &impl Playback) -> bool {
+        match condition {
+            Condition::Above(name, value) => self.floats.get(name).is_some_and(|&v| v > value),
+            Condition::Below(name, value) => self.floats.get(name).is_some_and(|&v| v < value),
+            Condition::Is(name, value) => {
+                self.bools.get(name).copied().unwrap_or_default() == value
+            }
+            Condition::Triggered(name) => self.triggers.contains(name),
+            Condition::Finished => animation.is_finished(),
+        }
+    }
+
+    fn next_state(&self, animation: &impl Playback) -> Option<&'static str> {
+        self.transitions
+            .iter()
+            .filter(|t| match t.from {
+                Some(from) => from == self.state,
+                None => t.to != self.state,
+            })
+            .find(|t| t.conditions.iter().all(|&c| self.holds(c, animation)))
+            .map(|t| t.to)
+    }
+}
+
+pub(super) fn update_controllers<P: Playback>(
+    mut query: Query<(&mut AnimationController, &mut P)>,
+) {
+    for (mut controller, mut animation) in query.iter_mut() {
+        if let Some(next) = controller.next_state(&*animation) {
+            controller.state = next;
+            animation.play(next);
+        }
+
+        if !controller.triggers.is_empty() {
+            controller.triggers.clear();
+        }
+    }
+}
@@ -0,0 +1,124 @@
+use std::time::Duration;
+
+use bevy::{prelude::*, sprite::Anchor};
+
+use super::{AnimatedSprite, AnimationTextureAtlasLayout};
+
+/// Blends between clips when an [`AnimatedSprite`] switches animation, by fading the outgoing
+/// frame out on a child sprite while the incoming clip fades in.
+#[derive(Component)]
+pub(crate) struct Crossfade {
+    duration: Duration,
+    /// Clip that was playing when last checked, a different clip means a switch happened
+    animation: Option<String>,
+    elapsed: Duration,
+    /// Child showing the outgoing frame while fading
+    ghost: Option<Entity>,
+    /// Alpha of the sprite before the fade started
+    alpha: f32,
+}
+
+impl Crossfade {
+    pub(crate) fn new(duration: Duration) -> Self {
+        Crossfade {
+            duration,
+            animation: None,
+            elapsed: Duration::ZERO,
+            ghost: None,
+            alpha: 1.0,
+        }
+    }
+}
+
+/// Marks the child sprite that shows the outgoing frame of a [`Crossfade`].
+#[derive(Component)]
+pub(super) struct CrossfadeGhost;
+
+/// Snapshots the outgoing frame of sprites that switched clips, so it has to run before the
+/// new clip's first frame is shown.
+pub(super) fn start_crossfades(
+    mut commands: Commands,
+    mut query: Query<(Entity, &AnimatedSprite, &mut Sprite, &mut Crossfade)>,
+    layouts: Res<Assets<AnimationTextureAtlasLayout>>,
+) {
+    for (entity, animation, mut sprite, mut crossfade) in query.iter_mut() {
+        let previous = crossfade
+            .animation
+            .replace(animation.animation_name.clone());
+        if previous.is_none_or(|previous| previous == animation.animation_name)
+            || crossfade.duration.is_zero()
+        {
+            continue;
+        }
+
+        let Some(anchor) = layouts
+            .get(&animation.layout)
+            .and_then(|layout| layout.sprite_anchor(&sprite))
+        else {
+            continue;
+        };
+
+        if crossfade.ghost.is_none() {
+            crossfade.alpha = sprite.color.alpha();
+        }
+
+        let mut ghost_sprite = sprite.clone();
+        ghost_sprite.color.set_alpha(crossfade.alpha);
+        let ghost = (
+            CrossfadeGhost,
+            ghost_sprite,
+            anchor,
+            // Slightly behind the incoming frame
+            Transform::from_xyz(0.0, 0.0, -0.001),
+        );
+
+        match crossfade.ghost {
+            Some(existing) => {
+                commands.entity(existing).insert(ghost);
+            }
+            None => {
+                crossfade.ghost = Some(commands.spawn((ghost, ChildOf(entity))).id());
+            }
+        }
+
+        crossfade.elapsed = Duration::ZERO;
+        sprite.color.set_alpha(0.0);
+    }
+}
+
+/// Interpolates alpha between the outgoing and incoming frames and removes the outgoing frame
+/// once the fade is over.
+pub(super) fn update_crossfades(
+    mut commands: Commands,
+    mut query: Query<(&mut Sprite, &mut Crossfade), Without<CrossfadeGhost>>,
+    mut ghosts: Query<(&mut Sprite, &mut Anchor), With<CrossfadeGhost>>,
+    time: Res<Time>,
+) {
+    for (mut sprite, mut crossfade) in query.iter_mut() {
+        let Some(ghost) = crossfade.ghost else {
+            continue;
+        };
+
+        crossfade.elapsed += time.delta();
+        let t = (crossfade.elapsed.as_secs_f32() / crossfade.duration.as_secs_f32()).min(1.0);
+        sprite.color.set_alpha(crossfade.alpha * t);
+
+        if t >= 1.0 {
+            commands.entity(ghost).despawn();
+            crossfade.ghost = None;
+            continue;
+        }
+
+        let Ok((mut ghost_sprite, mut anchor)) = ghosts.get_mut(ghost) else {
+            continue;
+        };
+
+        ghost_sprite.color.set_alpha(crossfade.alpha * (1.0 - t));
+
+        // Keep facing the same way as the incoming frame
+        if ghost_sprite.flip_x != sprite.flip_x {
+            ghost_sprite.flip_x = sprite.flip_x;
+            anchor.0.x = -anchor.0.x;
+        }
+    }
+}
@@ -0,0 +1,1002 @@
+//! Plays DragonBones JSON exports (`<name>_ske.json` with its `<name>_tex.json` atlas) as a
+//! hierarchy of bone entities with one sprite per slot, so animations authored in DragonBones
+//! don't have to be baked into a spritesheet first.
+//!
+//! DragonBones uses a y-down coordinate system with clockwise rotations in degrees, both are
+//! converted when loading. Skewing isn't supported, `skY` is used as the rotation.
+
+use bevy::{
+    asset::{AssetLoader, LoadContext, ParseAssetPathError, ReadAssetBytesError, io::Reader},
+    platform::collections::HashMap,
+    prelude::*,
+    sprite::Anchor,
+};
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+use super::{
+    AnimationFinished, AnimationSystems, RAW_LAYOUT_LABEL,
+    controller::{Playback, update_controllers},
+    formats::StarlingData,
+};
+use crate::game::InGameState;
+
+const SKELETON_SUFFIX: &str = "_ske";
+const ATLAS_SUFFIX: &str = "_tex.json";
+const DEFAULT_FRAME_RATE: f32 = 24.0;
+/// Depth between consecutive slots, slots later in the draw order are drawn on top
+const SLOT_Z_STEP: f32 = 0.01;
+
+pub(super) struct DragonBonesPlugin;
+
+impl Plugin for DragonBonesPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_asset::<DragonBonesSkeleton>()
+            .init_asset_loader::<DragonBonesLoader>()
+            .add_systems(
+                Update,
+                (respawn_modified_skeletons, spawn_skeleton_parts)
+                    .chain()
+                    .before(AnimationSystems),
+            )
+            .add_systems(
+                Update,
+                (update_controllers::<Skeleton>, animate_skeletons)
+                    .chain()
+                    .in_set(AnimationSystems)
+                    .run_if(in_state(InGameState::Running)),
+            );
+    }
+}
+
+#[derive(Error, Debug)]
+pub(crate) enum DragonBonesLoaderError {
+    #[error("could not read skeleton: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("could not parse skeleton json: {0}")]
+    Json(#[from] serde_json::Error),
+    #[error("invalid atlas path: {0}")]
+    AtlasPath(#[from] ParseAssetPathError),
+    #[error("could not read atlas: {0}")]
+    Atlas(#[from] ReadAssetBytesError),
+    #[error("armature {name} not found")]
+    MissingArmature { name: String },
+    #[error("unknown bone {name}")]
+    UnknownBone { name: String },
+    #[error("unknown slot {name}")]
+    UnknownSlot { name: String },
+}
+
+#[derive(Serialize, Deserialize, Default)]
+pub(crate) struct DragonBonesSettings {
+    /// Armature to load, the first armature in the file is used if not set
+    armature: Option<String>,
+}
+
+/// Loads a single armature from a DragonBones skeleton export.
+#[derive(Default)]
+pub(crate) struct DragonBonesLoader;
+
+impl AssetLoader for DragonBonesLoader {
+    type Asset = DragonBonesSkeleton;
+    type Settings = DragonBonesSettings;
+    type Error = DragonBonesLoaderError;
+
+    async fn load(
+        &self,
+        reader: &mut dyn Reader,
+        settings: &DragonBonesSettings,
+        load_context: &mut LoadContext<'_>,
+    ) -> Result<Self::Asset, Self::Error> {
+        let mut bytes = Vec::new();
+        reader.read_to_end(&mut bytes).await?;
+        let data: DragonBonesData = serde_json::from_slice(&bytes)?;
+
+        let armature = match &settings.armature {
+            Some(name) => data.armature.iter().find(|a| &a.name == name),
+            None => data.armature.first(),
+        }
+        .ok_or_else(|| DragonBonesLoaderError::MissingArmature {
+            name: settings
+                .armature
+                .clone()
+                .unwrap_or_else(|| data.name.clone()),
+        })?;
+
+        let stem = load_context
+            .path()
+            .file_stem()
+            .map(|stem| stem.to_string_lossy().into_owned())
+            .unwrap_or_default();
+        let name = stem.strip_suffix(SKELETON_SUFFIX).unwrap_or(&data.name);
+        let atlas_path = load_context
+            .asset_path()
+            .resolve_embed(&format!("{name}{ATLAS_SUFFIX}"))?;
+        let atlas: StarlingData =
+            serde_json::from_slice(&load_context.read_asset_bytes(atlas_path.clone()).await?)?;
+
+        let image = load_context.load(atlas_path.resolve_embed(&atlas.image_path)?);
+
+        let mut layout = TextureAtlasLayout::new_empty(UVec2::ZERO);
+        let mut regions = HashMap::default();
+        for texture in &atlas.sub_texture {
+            let index = layout.add_texture(texture.rect());
+            regions.insert(texture.name.clone(), (index, texture.anchor()));
+        }
+        let layout = load_context.add_labeled_asset(RAW_LAYOUT_LABEL.to_string(), layout);
+
+        let frame_rate = armature.frame_rate.unwrap_or(data.frame_rate);
+        DragonBonesSkeleton::build(armature, &regions, frame_rate, image, layout)
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["json"]
+    }
+}
+
+struct Bone {
+    parent: Option<usize>,
+    setup: Transform,
+}
+
+struct Display {
+    index: usize,
+    anchor: Vec2,
+    transform: Transform,
+}
+
+struct Slot {
+    bone: usize,
+    displays: Vec<Option<Display>>,
+    /// Index into `displays`, negative hides the slot
+    display_index: i32,
+}
+
+impl Slot {
+    fn display(&self, display_index: i32) -> Option<&Display> {
+        let index = usize::try_from(display_index).ok()?;
+        self.displays.get(index)?.as_ref()
+    }
+}
+
+struct Key<T> {
+    time: f32,
+    value: T,
+    /// Interpolate towards the next key, otherwise the value is held
+    tween: bool,
+}
+
+struct Track<T>(Vec<Key<T>>);
+
+impl<T: Copy> Track<T> {
+    fn sample(&self, time: f32, lerp: impl Fn(T, T, f32) -> T) -> Option<T> {
+        let next = self.0.partition_point(|key| key.time <= time);
+        let key = self.0.get(next.checked_sub(1)?)?;
+
+        match self.0.get(next) {
+            Some(to) if key.tween && to.time > key.time => Some(lerp(
+                key.value,
+                to.value,
+                (time - key.time) / (to.time - key.time),
+            )),
+            _ => Some(key.value),
+        }
+    }
+}
+
+struct BoneTimeline {
+    translate: Option<Track<Vec2>>,
+    rotate: Option<Track<f32>>,
+    scale: Option<Track<Vec2>>,
+}
+
+struct SkeletonClip {
+    duration: f32,
+    /// Zero repeats forever
+    play_times: u32,
+    bones: Vec<(usize, BoneTimeline)>,
+    slots: Vec<(usize, Track<i32>)>,
+}
+
+#[derive(Asset, TypePath)]
+pub(crate) struct DragonBonesSkeleton {
+    #[dependency]
+    image: Handle<Image>,
+    layout: Handle<TextureAtlasLayout>,
+    bones: Vec<Bone>,
+    slots: Vec<Slot>,
+    animations: HashMap<String, SkeletonClip>,
+    default_animation: Option<String>,
+}
+
+impl DragonBonesSkeleton {
+    fn build(
+        armature: &ArmatureData,
+        regions: &HashMap<String, (usize, Vec2)>,
+        frame_rate: f32,
+        image: Handle<Image>,
+        layout: Handle<TextureAtlasLayout>,
+    ) -> Result<Self, DragonBonesLoaderError> {
+        let bone_index = |name: &str| {
+            armature
+                .bone
+                .iter()
+                .position(|b| b.name == name)
+                .ok_or_else(|| DragonBonesLoaderError::UnknownBone {
+                    name: name.to_string(),
+                })
+        };
+        let slot_index = |name: &str| {
+            armature
+                .slot
+                .iter()
+                .position(|s| s.name == name)
+                .ok_or_else(|| DragonBonesLoaderError::UnknownSlot {
+                    name: name.to_string(),
+                })
+        };
+
+        let bones = armature
+            .bone
+            .iter()
+            .map(|bone| {
+                Ok(Bone {
+                    parent: bone.parent.as_deref().map(bone_index).transpose()?,
+                    setup: bone.transform.to_transform(),
+                })
+            })
+            .collect::<Result<Vec<_>, DragonBonesLoaderError>>()?;
+
+        let mut slots = armature
+            .slot
+            .iter()
+            .map(|slot| {
+                Ok(Slot {
+                    bone: bone_index(&slot.parent)?,
+                    displays: Vec::new(),
+                    display_index: slot.display_index,
+                })
+            })
+            .collect::<Result<Vec<_>, DragonBonesLoaderError>>()?;
+
+        for skin_slot in armature.skin.iter().flat_map(|skin| &skin.slot) {
+            let slot = &mut slots[slot_index(&skin_slot.name)?];
+            slot.displays = skin_slot
+                .display
+                .iter()
+                .map(|display| {
+                    let region = display.path.as_ref().unwrap_or(&display.name);
+                    regions.get(region).map(|&(index, anchor)| Display {
+                        index,
+                        anchor,
+                        transform: display.transform.to_transform(),
+                    })
+                })
+                .collect();
+        }
+
+        let mut animations = HashMap::default();
+        for animation in &armature.animation {
+            let bones = animation
+                .bone
+                .iter()
+                .map(|timeline| {
+                    Ok((
+                        bone_index(&timeline.name)?,
+                        BoneTimeline {
+                            translate: track(&timeline.translate_frame, frame_rate, |f| {
+                                Vec2::new(f.x.unwrap_or(0.0), -f.y.unwrap_or(0.0))
+                            }),
+                            rotate: rotation_track(&timeline.rotate_frame, frame_rate),
+                            scale: track(&timeline.scale_frame, frame_rate, |f| {
+                                Vec2::new(f.x.unwrap_or(1.0), f.y.unwrap_or(1.0))
+                            }),
+                        },
+                    ))
+                })
+                .collect::<Result<Vec<_>, DragonBonesLoaderError>>()?;
+
+            let slots = animation
+                .slot
+                .iter()
+                .filter_map(|timeline| {
+                    let frames = track(&timeline.display_frame, frame_rate, |f| f.value)?;
+                    Some(slot_index(&timeline.name).map(|index| (index, frames)))
+                })
+                .collect::<Result<Vec<_>, DragonBonesLoaderError>>()?;
+
+            animations.insert(
+                animation.name.clone(),
+                SkeletonClip {
+                    duration: animation.duration as f32 / frame_rate,
+                    play_times: animation.play_times,
+                    bones,
+                    slots,
+                },
+            );
+        }
+
+        Ok(DragonBonesSkeleton {
+            image,
+            layout,
+            bones,
+            slots,
+            animations,
+            default_animation: armature
+                .default_actions
+                .iter()
+                .find_map(|action| action.goto_and_play.clone()),
+        })
+    }
+}
+
+fn track<F, T>(frames: &[F], frame_rate: f32, value: impl Fn(&F) -> T) -> Option<Track<T>>
+where
+    F: TimelineFrame,
+{
+    if frames.is_empty() {
+        return None;
+    }
+
+    let mut time = 0;
+    let keys = frames
+        .iter()
+        .map(|frame| {
+            let key = Key {
+                time: time as f32 / frame_rate,
+                value: value(frame),
+                tween: frame.tweens(),
+            };
+            time += frame.duration();
+            key
+        })
+        .collect();
+
+    Some(Track(keys))
+}
+
+/// Rotation keys unwrapped so that interpolating between them turns the way DragonBones does,
+/// the short way unless the key the tween starts from sets `clockwise`.
+fn rotation_track(frames: &[TweenFrame], frame_rate: f32) -> Option<Track<f32>> {
+    let mut track = track(frames, frame_rate, |f| f.rotate)?;
+
+    for (idx, pair) in frames.windows(2).enumerate() {
+        let from = track.0[idx].value;
+        track.0[idx + 1].value = from + turn(pair[0].rotate, pair[1].rotate, pair[0].clockwise);
+    }
+
+    for key in &mut track.0 {
+        key.value = -key.value.to_radians();
+    }
+
+    Some(track)
+}
+
+/// Degrees turned going from `from` to `to`. A positive `clockwise` turns clockwise and a
+/// negative one counterclockwise, adding a full turn for every step past one.
+fn turn(from: f32, to: f32, clockwise: i32) -> f32 {
+    let delta = (to - from).rem_euclid(360.0);
+    let extra_turns = 360.0 * (clockwise.unsigned_abs().max(1) - 1) as f32;
+
+    match clockwise.signum() {
+        0 if delta > 180.0 => delta - 360.0,
+        0 => delta,
+        1 => delta + extra_turns,
+        _ if delta > 0.0 => delta - 360.0 - extra_turns,
+        _ => -extra_turns,
+    }
+}
+
+/// Plays a [`DragonBonesSkeleton`] by spawning its bones as children of this entity.
+#[derive(Component)]
+#[require(Transform, Visibility)]
+pub(crate) struct Skeleton {
+    skeleton: Handle<DragonBonesSkeleton>,
+    animation: Option<String>,
+    elapsed: f32,
+    finished: bool,
+}
+
+impl Skeleton {
+    /// Plays `animation`, or the armature's default animation if `None`.
+    pub(crate) fn new(skeleton: Handle<DragonBonesSkeleton>, animation: Option<&str>) -> Self {
+        Skeleton {
+            skeleton,
+            animation: animation.map(str::to_string),
+            elapsed: 0.0,
+            finished: false,
+        }
+    }
+
+    /// Switches to `animation` from its start. Does nothing if the animation is already playing.
+    pub(crate) fn play(&mut self, animation: &str) {
+        if self.animation.as_deref() == Some(animation) {
+            return;
+        }
+
+        self.animation = Some(animation.to_string());
+        self.elapsed = 0.0;
+        self.finished = false;
+    }
+}
+
+impl Playback for Skeleton {
+    fn play(&mut self, name: &str) {
+        Skeleton::play(self, name);
+    }
+
+    fn is_finished(&self) -> bool {
+        self.finished
+    }
+}
+
+#[derive(Component)]
+struct SkeletonParts {
+    bones: Vec<Entity>,
+    slots: Vec<Entity>,
+}
+
+fn spawn_skeleton_parts(
+    mut commands: Commands,
+    mut query: Query<(Entity, &mut Skeleton), Without<SkeletonParts>>,
+    skeletons: Res<Assets<DragonBonesSkeleton>>,
+) {
+    for (entity, mut skeleton) in query.iter_mut() {
+        let Some(data) = skeletons.get(&skeleton.skeleton) else {
+            continue;
+        };
+
+        if skeleton.animation.is_none() {
+            skeleton.animation = data.default_animation.clone();
+        }
+
+        let bones: Vec<Entity> = data
+            .bones
+            .iter()
+            .map(|bone| commands.spawn((bone.setup, Visibility::default())).id())
+            .collect();
+
+        for (bone, &bone_entity) in data.bones.iter().zip(&bones) {
+            let parent = bone.parent.map_or(entity, |parent| bones[parent]);
+            commands.entity(bone_entity).insert(ChildOf(parent));
+        }
+
+        let slots = data
+            .slots
+            .iter()
+            .enumerate()
+            .map(|(order, slot)| {
+                let index = slot.display(slot.display_index).map_or(0, |d| d.index);
+                commands
+                    .spawn((
+                        Sprite::from_atlas_image(
+                            data.image.clone(),
+                            TextureAtlas {
+                                layout: data.layout.clone(),
+                                index,
+                            },
+                        ),
+                        slot_display(slot, slot.display_index, order),
+                        ChildOf(bones[slot.bone]),
+                    ))
+                    .id()
+            })
+            .collect();
+
+        commands
+            .entity(entity)
+            .insert(SkeletonParts { bones, slots });
+    }
+}
+
+/// Index of the display currently shown by a slot's sprite.
+#[derive(Component)]
+struct SlotDisplay(i32);
+
+fn slot_display(slot: &Slot, display_index: i32, order: usize) -> impl Bundle + use<> {
+    let z = order as f32 * SLOT_Z_STEP;
+
+    match slot.display(display_index) {
+        Some(display) => (
+            display
+                .transform
+                .with_translation(display.transform.translation.with_z(z)),
+            Anchor(display.anchor),
+            Visibility::Inherited,
+            SlotDisplay(display_index),
+        ),
+        None => (
+            Transform::from_xyz(0.0, 0.0, z),
+            Anchor::default(),
+            Visibility::Hidden,
+            SlotDisplay(display_index),
+        ),
+    }
+}
+
+fn respawn_modified_skeletons(
+    mut commands: Commands,
+    mut events: MessageReader<AssetEvent<DragonBonesSkeleton>>,
+    query: Query<(Entity, &Skeleton, &SkeletonParts)>,
+) {
+    for event in events.read() {
+        let AssetEvent::Modified { id } = event else {
+            continue;
+        };
+
+        for (entity, skeleton, parts) in query.iter() {
+            if skeleton.skeleton.id() != *id {
+                continue;
+            }
+
+            for &bone in &parts.bones {
+                commands.entity(bone).try_despawn();
+            }
+            commands.entity(entity).remove::<SkeletonParts>();
+        }
+    }
+}
+
+fn animate_skeletons(
+    mut commands: Commands,
+    mut query: Query<(Entity, &mut Skeleton, &SkeletonParts)>,
+    mut transforms: Query<&mut Transform>,
+    mut sprites: Query<(&mut Sprite, &SlotDisplay)>,
+    skeletons: Res<Assets<DragonBonesSkeleton>>,
+    time: Res<Time>,
+) {
+    for (entity, skeleton, parts) in query.iter_mut() {
+        let skeleton = skeleton.into_inner();
+        let Some(data) = skeletons.get(&skeleton.skeleton) else {
+            continue;
+        };
+
+        let Some(name) = &skeleton.animation else {
+            continue;
+        };
+
+        let Some(clip) = data.animations.get(name) else {
+            continue;
+        };
+
+        if !skeleton.finished {
+            skeleton.elapsed += time.delta_secs();
+        }
+
+        let total = clip.duration * clip.play_times as f32;
+        if clip.play_times > 0 && skeleton.elapsed >= total && !skeleton.finished {
+            skeleton.finished = true;
+            commands.trigger(AnimationFinished {
+                entity,
+                name: name.clone(),
+            });
+        }
+
+        let local_time = if skeleton.finished || clip.duration <= 0.0 {
+            clip.duration
+        } else {
+            skeleton.elapsed % clip.duration
+        };
+
+        for (bone_index, timeline) in &clip.bones {
+            let Ok(mut transform) = transforms.get_mut(parts.bones[*bone_index]) else {
+                continue;
+            };
+
+            let setup = data.bones[*bone_index].setup;
+            let translate = timeline
+                .translate
+                .as_ref()
+                .and_then(|t| t.sample(local_time, Vec2::lerp))
+                .unwrap_or(Vec2::ZERO);
+            let rotate = timeline
+                .rotate
+                .as_ref()
+                .and_then(|t| t.sample(local_time, |from, to, s| from.lerp(to, s)))
+                .unwrap_or(0.0);
+            let scale = timeline
+                .scale
+                .as_ref()
+                .and_then(|t| t.sample(local_time, Vec2::lerp))
+                .unwrap_or(Vec2::ONE);
+
+            *transform = Transform {
+                translation: setup.translation + translate.extend(0.0),
+                rotation: setup.rotation * Quat::from_rotation_z(rotate),
+                scale: setup.scale * scale.extend(1.0),
+            };
+        }
+
+        for (slot_index, timeline) in &clip.slots {
+            let Some(display_index) = timeline.sample(local_time, |from, _, _| from) else {
+                continue;
+            };
+
+            let slot_entity = parts.slots[*slot_index];
+            let Ok((mut sprite, current)) = sprites.get_mut(slot_entity) else {
+                continue;
+            };
+
+            if current.0 == display_index {
+                continue;
+            }
+
+            let slot = &data.slots[*slot_index];
+            if let Some(display) = slot.display(display_index)
+                && let Some(atlas) = &mut sprite.texture_atlas
+            {
+                atlas.index = display.index;
+            }
+
+            commands
+                .entity(slot_entity)
+                .insert(slot_display(slot, display_index, *slot_index));
+        }
+    }
+}
+
+trait TimelineFrame {
+    fn duration(&self) -> u32;
+
+    fn tweens(&self) -> bool;
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct DragonBonesData {
+    #[serde(default)]
+    name: String,
+    #[serde(default = "default_frame_rate")]
+    frame_rate: f32,
+    armature: Vec<ArmatureData>,
+}
+
+fn default_frame_rate() -> f32 {
+    DEFAULT_FRAME_RATE
+}
+
+fn one() -> f32 {
+    1.0
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct ArmatureData {
+    name: String,
+    frame_rate: Option<f32>,
+    #[serde(default)]
+    bone: Vec<BoneData>,
+    #[serde(default)]
+    slot: Vec<SlotData>,
+    #[serde(default)]
+    skin: Vec<SkinData>,
+    #[serde(default)]
+    animation: Vec<AnimationData>,
+    #[serde(default)]
+    default_actions: Vec<ActionData>,
+}
+
+#[derive(Deserialize, Default)]
+#[serde(rename_all = "camelCase")]
+struct TransformData {
+    #[serde(default)]
+    x: f32,
+    #[serde(default)]
+    y: f32,
+    #[serde(default)]
+    sk_y: f32,
+    #[serde(default = "one")]
+    sc_x: f32,
+    #[serde(default = "one")]
+    sc_y: f32,
+}
+
+impl TransformData {
+    fn to_transform(&self) -> Transform {
+        Transform {
+            translation: Vec3::new(self.x, -self.y, 0.0),
+            rotation: Quat::from_rotation_z(-self.sk_y.to_radians()),
+            scale: Vec3::new(self.sc_x, self.sc_y, 1.0),
+        }
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct BoneData {
+    name: String,
+    parent: Option<String>,
+    #[serde(default)]
+    transform: TransformData,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct SlotData {
+    name: String,
+    parent: String,
+    #[serde(default)]
+    display_index: i32,
+}
+
+#[derive(Deserialize)]
+struct SkinData {
+    #[serde(default)]
+    slot: Vec<SkinSlotData>,
+}
+
+#[derive(Deserialize)]
+struct SkinSlotData {
+    name: String,
+    #[serde(default)]
+    display: Vec<DisplayData>,
+}
+
+#[derive(Deserialize)]
+struct DisplayData {
+    name: String,
+    path: Option<String>,
+    #[serde(default)]
+    transform: TransformData,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct AnimationData {
+    name: String,
+    #[serde(default)]
+    duration: u32,
+    #[serde(default)]
+    play_times: u32,
+    #[serde(default)]
+    bone: Vec<BoneTimelineData>,
+    #[serde(default)]
+    slot: Vec<SlotTimelineData>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct BoneTimelineData {
+    name: String,
+    #[serde(default)]
+    translate_frame: Vec<TweenFrame>,
+    #[serde(default)]
+    rotate_frame: Vec<TweenFrame>,
+    #[serde(default)]
+    scale_frame: Vec<TweenFrame>,
+}
+
+/// A bone keyframe, `tween_easing` is absent for frames that hold their value. Easing curves
+/// are played back linearly.
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct TweenFrame {
+    #[serde(default)]
+    duration: u32,
+    tween_easing: Option<f32>,
+    x: Option<f32>,
+    y: Option<f32>,
+    #[serde(default)]
+    rotate: f32,
+    /// Direction of the rotation towards the next key, see [`turn`]
+    #[serde(default)]
+    clockwise: i32,
+}
+
+impl TimelineFrame for TweenFrame {
+    fn duration(&self) -> u32 {
+        self.duration
+    }
+
+    fn tweens(&self) -> bool {
+        self.tween_easing.is_some()
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct SlotTimelineData {
+    name: String,
+    #[serde(default)]
+    display_frame: Vec<DisplayFrame>,
+}
+
+#[derive(Deserialize)]
+struct DisplayFrame {
+    #[serde(default)]
+    duration: u32,
+    #[serde(default)]
+    value: i32,
+}
+
+impl TimelineFrame for DisplayFrame {
+    fn duration(&self) -> u32 {
+        self.duration
+    }
+
+    fn tweens(&self) -> bool {
+        false
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct ActionData {
+    goto_and_play: Option<String>,
+}
+
+#[cfg(test)]
+mod tests {
+    use std::f32::consts::PI;
+
+    use super::*;
+    use crate::animation::tests::wait_for;
+
+    fn app() -> App {
+        let mut app = crate::animation::tests::app();
+        app.init_asset::<DragonBonesSkeleton>()
+            .init_asset_loader::<DragonBonesLoader>();
+        app
+    }
+
+    fn load_worker() -> (App, Handle<DragonBonesSkeleton>) {
+        let mut app = app();
+        let handle = app
+            .world()
+            .resource::<AssetServer>()
+            .load("dragonbones/worker_ske.json");
+        wait_for(&mut app, &handle).unwrap();
+        (app, handle)
+    }
+
+    fn clip<'a>(skeleton: &'a DragonBonesSkeleton, name: &str) -> &'a SkeletonClip {
+        skeleton.animations.get(name).unwrap()
+    }
+
+    #[test]
+    fn loads_bones_in_y_up_space() {
+        let (app, handle) = load_worker();
+        let skeleton = app
+            .world()
+            .resource::<Assets<DragonBonesSkeleton>>()
+            .get(&handle)
+            .unwrap();
+
+        let parents: Vec<_> = skeleton.bones.iter().map(|bone| bone.parent).collect();
+        assert_eq!(parents, [None, Some(0), Some(1)]);
+        assert_eq!(
+            skeleton.bones[1].setup.translation,
+            Vec3::new(0.0, 60.0, 0.0)
+        );
+        assert_eq!(
+            skeleton.bones[2].setup.translation,
+            Vec3::new(10.0, 20.0, 0.0)
+        );
+        assert!(
+            skeleton.bones[2]
+                .setup
+                .rotation
+                .abs_diff_eq(Quat::from_rotation_z(-PI / 2.0), 1e-5)
+        );
+    }
+
+    #[test]
+    fn resolves_slot_displays_from_the_atlas() {
+        let (app, handle) = load_worker();
+        let skeleton = app
+            .world()
+            .resource::<Assets<DragonBonesSkeleton>>()
+            .get(&handle)
+            .unwrap();
+        let layouts = app.world().resource::<Assets<TextureAtlasLayout>>();
+        assert_eq!(layouts.get(&skeleton.layout).unwrap().textures.len(), 3);
+
+        let body = skeleton.slots[0].display(0).unwrap();
+        assert_eq!(body.index, 0);
+        assert_eq!(body.transform.translation, Vec3::new(0.0, -5.0, 0.0));
+
+        // Displays can name a texture other than their own through `path`
+        assert_eq!(skeleton.slots[1].bone, 2);
+        assert_eq!(skeleton.slots[1].display(0).unwrap().index, 1);
+
+        let helmet = &skeleton.slots[2];
+        assert_eq!(helmet.display_index, -1);
+        assert!(helmet.display(helmet.display_index).is_none());
+        assert_eq!(helmet.display(0).unwrap().index, 2);
+        assert!(
+            helmet.display(1).is_none(),
+            "hat has no texture in the atlas"
+        );
+    }
+
+    #[test]
+    fn loads_animations_at_the_armature_frame_rate() {
+        let (app, handle) = load_worker();
+        let skeleton = app
+            .world()
+            .resource::<Assets<DragonBonesSkeleton>>()
+            .get(&handle)
+            .unwrap();
+        assert_eq!(skeleton.default_animation.as_deref(), Some("swing"));
+
+        let swing = clip(skeleton, "swing");
+        assert_eq!(swing.duration, 1.0);
+        assert_eq!(swing.play_times, 0);
+
+        let (slot, helmet) = &swing.slots[0];
+        assert_eq!(*slot, 2);
+        assert_eq!(helmet.sample(0.25, |from, _, _| from), Some(-1));
+        assert_eq!(helmet.sample(0.75, |from, _, _| from), Some(0));
+
+        let duck = clip(skeleton, "duck");
+        assert_eq!(duck.duration, 0.5);
+        assert_eq!(duck.play_times, 1);
+        let (bone, timeline) = &duck.bones[0];
+        assert_eq!(*bone, 1);
+        assert_eq!(
+            timeline
+                .translate
+                .as_ref()
+                .unwrap()
+                .sample(0.25, Vec2::lerp),
+            Some(Vec2::new(2.0, -6.0))
+        );
+    }
+
+    #[test]
+    fn rotates_the_short_way_unless_clockwise() {
+        let (app, handle) = load_worker();
+        let skeleton = app
+            .world()
+            .resource::<Assets<DragonBonesSkeleton>>()
+            .get(&handle)
+            .unwrap();
+        let (_, timeline) = &clip(skeleton, "swing").bones[0];
+        let rotate = timeline.rotate.as_ref().unwrap();
+        let sample = |time| rotate.sample(time, f32::lerp).unwrap();
+
+        // -170° to 170° passes through 180°, not 0°
+        assert!((sample(0.25) - PI).abs() < 1e-4);
+        // 170° clockwise to 160° goes nearly all the way around, through -15°
+        assert!((sample(0.75) - 15f32.to_radians()).abs() < 1e-4);
+        assert!((sample(1.0) + 160f32.to_radians()).abs() < 1e-4);
+    }
+
+    #[test]
+    fn turns_in_the_requested_direction() {
+        assert_eq!(turn(-170.0, 170.0, 0), -20.0);
+        assert_eq!(turn(170.0, -170.0, 0), 20.0);
+        assert_eq!(turn(0.0, 90.0, 0), 90.0);
+        assert_eq!(turn(0.0, 90.0, 1), 90.0);
+        assert_eq!(turn(0.0, 90.0, 2), 450.0);
+        assert_eq!(turn(0.0, 90.0, -1), -270.0);
+        assert_eq!(turn(0.0, 90.0, -2), -630.0);
+        assert_eq!(turn(45.0, 45.0, 0), 0.0);
+        assert_eq!(turn(45.0, 45.0, -2), -360.0);
+    }
+
+    #[test]
+    fn fails_on_missing_armature() {
+        let mut app = app();
+        let handle: Handle<DragonBonesSkeleton> =
+            app.world().resource::<AssetServer>().load_with_settings(
+                "dragonbones/worker_ske.json",
+                |settings: &mut DragonBonesSettings| settings.armature = Some("boss".to_string()),
+            );
+
+        let error = wait_for(&mut app, &handle).unwrap_err();
+        assert!(error.contains("armature boss not found"), "{error}");
+    }
+
+    #[test]
+    fn fails_on_slot_of_unknown_bone() {
+        let mut app = app();
+        let handle: Handle<DragonBonesSkeleton> = app
+            .world()
+            .resource::<AssetServer>()
+            .load("dragonbones/broken_ske.json");
+
+        let error = wait_for(&mut app, &handle).unwrap_err();
+        assert!(error.contains("unknown bone spine"), "{error}");
+    }
+}
@@ -0,0 +1,614 @@
+//! Spritesheet JSON formats understood by the spritesheet loader. Every format is imported
+//! into an [`ImportedSheet`], which the loader turns into an
+//! [`AnimationTextureAtlasLayout`](super::AnimationTextureAtlasLayout).
+
+use bevy::prelude::*;
+use serde::{Deserialize, Deserializer, Serialize, de};
+use std::{collections::BTreeMap, fmt, path::Path, time::Duration};
+
+use super::{ClipSettings, PlaybackMode, SpritesheetLoaderError, SpritesheetProblem};
+
+const ASEPRITE_EXTENSION: &str = ".aseprite.json";
+const TEXTURE_PACKER_EXTENSION: &str = ".tp.json";
+
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Debug)]
+#[serde(rename_all = "snake_case")]
+pub(crate) enum SpritesheetFormat {
+    /// Starling/DragonBones XML-style atlas with a `SubTexture` array, as exported by
+    /// DragonBones
+    Starling,
+    /// Aseprite JSON export, animations come from `frameTags`
+    Aseprite,
+    /// TexturePacker JSON (hash or array) export
+    TexturePacker,
+}
+
+impl SpritesheetFormat {
+    /// Picks the format from a `.aseprite.json` or `.tp.json` extension, falling back to
+    /// the shape of the JSON.
+    pub(super) fn detect(path: &Path, json: &serde_json::Value) -> Self {
+        let file_name = path
+            .file_name()
+            .map(|name| name.to_string_lossy().to_lowercase())
+            .unwrap_or_default();
+
+        if file_name.ends_with(ASEPRITE_EXTENSION) {
+            return SpritesheetFormat::Aseprite;
+        }
+
+        if file_name.ends_with(TEXTURE_PACKER_EXTENSION) {
+            return SpritesheetFormat::TexturePacker;
+        }
+
+        if json.get("SubTexture").is_some() {
+            return SpritesheetFormat::Starling;
+        }
+
+        let meta = json.get("meta");
+        let app = meta
+            .and_then(|meta| meta.get("app"))
+            .and_then(|app| app.as_str())
+            .unwrap_or_default();
+        if app.contains("aseprite") || meta.is_some_and(|meta| meta.get("frameTags").is_some()) {
+            return SpritesheetFormat::Aseprite;
+        }
+
+        SpritesheetFormat::TexturePacker
+    }
+
+    pub(super) fn import(
+        self,
+        json: serde_json::Value,
+    ) -> Result<ImportedSheet, SpritesheetLoaderError> {
+        Ok(match self {
+            SpritesheetFormat::Starling => serde_json::from_value::<StarlingData>(json)?.import(),
+            SpritesheetFormat::Aseprite => serde_json::from_value::<AsepriteData>(json)?.import(),
+            SpritesheetFormat::TexturePacker => {
+                serde_json::from_value::<TexturePackerData>(json)?.import()
+            }
+        })
+    }
+}
+
+pub(super) struct ImportedFrame {
+    pub(super) name: String,
+    pub(super) rect: URect,
+    pub(super) anchor: Vec2,
+    pub(super) duration: Option<Duration>,
+}
+
+pub(super) struct ImportedAnimation {
+    /// Indices into [`ImportedSheet::frames`] in playback order
+    pub(super) frames: Vec<usize>,
+    pub(super) mode: Option<PlaybackMode>,
+}
+
+pub(super) struct ImportedSheet {
+    pub(super) image_path: String,
+    /// Size of the image, if the format records it
+    pub(super) image_size: Option<UVec2>,
+    pub(super) frames: Vec<ImportedFrame>,
+    pub(super) animations: BTreeMap<String, ImportedAnimation>,
+    /// Settings embedded in the spritesheet itself
+    pub(super) settings: BTreeMap<String, ClipSettings>,
+    /// Problems found while importing, offending frames and animations are left out
+    pub(super) problems: Vec<SpritesheetProblem>,
+}
+
+/// Anchor that places the center of the untrimmed `canvas` at the sprite's origin, for a crop
+/// of `size` whose top-left corner is at `offset` within the canvas.
+fn trimmed_anchor(size: Vec2, canvas: Vec2, offset: Vec2) -> Vec2 {
+    if canvas.cmpeq(Vec2::ZERO).any() || size.cmpeq(Vec2::ZERO).any() {
+        return Vec2::ZERO;
+    }
+
+    // Canvas center relative to the crop's top-left corner, y pointing down
+    let center = canvas / 2.0 - offset;
+
+    Vec2::new(center.x / size.x - 0.5, 0.5 - center.y / size.y)
+}
+
+/// Groups frames named `<animation>_<position>` (or `<animation>/<position>`, with an optional
+/// file extension) into animations ordered by position.
+fn group_by_name(
+    names: &[&str],
+    problems: &mut Vec<SpritesheetProblem>,
+) -> BTreeMap<String, ImportedAnimation> {
+    let mut temp_indices_map: BTreeMap<String, Vec<(usize, usize)>> = BTreeMap::new();
+
+    for (idx, &full_name) in names.iter().enumerate() {
+        let name = match full_name.rsplit_once('.') {
+            Some((stem, extension)) if extension.chars().all(|c| c.is_ascii_alphabetic()) => stem,
+            _ => full_name,
+        };
+
+        let Some((animation, position)) = name
+            .rsplit_once(['_', '/'])
+            .filter(|(animation, _)| !animation.is_empty())
+        else {
+            problems.push(SpritesheetProblem::BadNameFormat {
+                name: full_name.to_string(),
+            });
+            continue;
+        };
+
+        let Ok(position) = position.parse::<usize>() else {
+            problems.push(SpritesheetProblem::NonNumericFrameIndex {
+                name: full_name.to_string(),
+            });
+            continue;
+        };
+
+        temp_indices_map
+            .entry(animation.to_string())
+            .or_default()
+            .push((position, idx));
+    }
+
+    let mut animations = BTreeMap::new();
+    for (animation, mut list) in temp_indices_map.into_iter() {
+        list.sort_by_key(|e| e.0);
+
+        for pair in list.windows(2) {
+            let [(previous, _), (position, idx)] = *pair else {
+                continue;
+            };
+
+            if position == previous {
+                problems.push(SpritesheetProblem::DuplicateFrameIndex {
+                    name: names[idx].to_string(),
+                    animation: animation.clone(),
+                    position,
+                });
+            } else if position > previous + 1 {
+                problems.push(SpritesheetProblem::FrameGap {
+                    name: names[idx].to_string(),
+                    animation: animation.clone(),
+                    missing: previous + 1,
+                });
+            }
+        }
+
+        list.dedup_by_key(|e| e.0);
+        animations.insert(
+            animation,
+            ImportedAnimation {
+                frames: list.into_iter().map(|e| e.1).collect(),
+                mode: None,
+            },
+        );
+    }
+
+    animations
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub(super) struct StarlingData {
+    pub(super) image_path: String,
+    #[serde(rename = "SubTexture")]
+    pub(super) sub_texture: Vec<SubTexture>,
+    name: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    width: Option<u32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    height: Option<u32>,
+    /// Playback settings keyed by animation name, overridden by the sidecar file
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    animations: BTreeMap<String, ClipSettings>,
+}
+
+impl StarlingData {
+    fn import(self) -> ImportedSheet {
+        let mut problems = Vec::new();
+        let names: Vec<&str> = self.sub_texture.iter().map(|t| t.name.as_str()).collect();
+        let animations = group_by_name(&names, &mut problems);
+        let frames = self
+            .sub_texture
+            .iter()
+            .map(|texture| ImportedFrame {
+                name: texture.name.clone(),
+                rect: texture.rect(),
+                anchor: texture.anchor(),
+                duration: None,
+            })
+            .collect();
+
+        ImportedSheet {
+            image_path: self.image_path,
+            image_size: self.width.zip(self.height).map(UVec2::from),
+            frames,
+            animations,
+            settings: self.animations,
+            problems,
+        }
+    }
+}
+
+/// Trimmed textures are cropped to their opaque pixels, `frame_x` and `frame_y` are the
+/// negated position of the crop within the original `frame_width` x `frame_height` canvas.
+/// Untrimmed textures omit the frame fields.
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub(super) struct SubTexture {
+    #[serde(default)]
+    frame_width: i64,
+    y: i64,
+    #[serde(default)]
+    frame_height: i64,
+    width: i64,
+    #[serde(default)]
+    frame_x: i64,
+    height: i64,
+    pub(super) name: String,
+    #[serde(default)]
+    frame_y: i64,
+    x: i64,
+}
+
+impl SubTexture {
+    pub(super) fn rect(&self) -> URect {
+        URect::from_corners(
+            UVec2::new(self.x as u32, self.y as u32),
+            UVec2::new((self.x + self.width) as u32, (self.y + self.height) as u32),
+        )
+    }
+
+    pub(super) fn anchor(&self) -> Vec2 {
+        trimmed_anchor(
+            Vec2::new(self.width as f32, self.height as f32),
+            Vec2::new(self.frame_width as f32, self.frame_height as f32),
+            Vec2::new(-self.frame_x as f32, -self.frame_y as f32),
+        )
+    }
+}
+
+#[derive(Deserialize)]
+struct JsonRect {
+    x: u32,
+    y: u32,
+    w: u32,
+    h: u32,
+}
+
+#[derive(Deserialize)]
+struct JsonSize {
+    w: u32,
+    h: u32,
+}
+
+impl From<JsonSize> for UVec2 {
+    fn from(size: JsonSize) -> Self {
+        UVec2::new(size.w, size.h)
+    }
+}
+
+/// A frame as written by both Aseprite and TexturePacker.
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct HashFrame {
+    frame: JsonRect,
+    #[serde(default)]
+    rotated: bool,
+    sprite_source_size: Option<JsonRect>,
+    source_size: Option<JsonSize>,
+    /// Milliseconds, Aseprite only
+    duration: Option<u64>,
+}
+
+impl HashFrame {
+    fn import(&self, name: &str, problems: &mut Vec<SpritesheetProblem>) -> ImportedFrame {
+        if self.rotated {
+            problems.push(SpritesheetProblem::RotatedFrame {
+                name: name.to_string(),
+            });
+        }
+
+        let size = Vec2::new(self.frame.w as f32, self.frame.h as f32);
+        let anchor = match (&self.sprite_source_size, &self.source_size) {
+            (Some(crop), Some(canvas)) => trimmed_anchor(
+                size,
+                Vec2::new(canvas.w as f32, canvas.h as f32),
+                Vec2::new(crop.x as f32, crop.y as f32),
+            ),
+            _ => Vec2::ZERO,
+        };
+
+        ImportedFrame {
+            name: name.to_string(),
+            rect: URect::new(
+                self.frame.x,
+                self.frame.y,
+                self.frame.x + self.frame.w,
+                self.frame.y + self.frame.h,
+            ),
+            anchor,
+            duration: self.duration.map(Duration::from_millis),
+        }
+    }
+}
+
+/// Frames keyed by name, either as a JSON object (hash export) or as an array of objects with
+/// a `filename` (array export). Order is preserved, Aseprite relies on it for frame tags.
+struct Frames(Vec<(String, HashFrame)>);
+
+impl<'de> Deserialize<'de> for Frames {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        struct FramesVisitor;
+
+        #[derive(Deserialize)]
+        struct NamedFrame {
+            filename: String,
+            #[serde(flatten)]
+            frame: HashFrame,
+        }
+
+        impl<'de> de::Visitor<'de> for FramesVisitor {
+            type Value = Frames;
+
+            fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+                formatter.write_str("a map or array of frames")
+            }
+
+            fn visit_map<A: de::MapAccess<'de>>(self, mut map: A) -> Result<Frames, A::Error> {
+                let mut frames = Vec::new();
+                while let Some(entry) = map.next_entry()? {
+                    frames.push(entry);
+                }
+                Ok(Frames(frames))
+            }
+
+            fn visit_seq<A: de::SeqAccess<'de>>(self, mut seq: A) -> Result<Frames, A::Error> {
+                let mut frames = Vec::new();
+                while let Some(NamedFrame { filename, frame }) = seq.next_element()? {
+                    frames.push((filename, frame));
+                }
+                Ok(Frames(frames))
+            }
+        }
+
+        deserializer.deserialize_any(FramesVisitor)
+    }
+}
+
+impl Frames {
+    fn import(&self, problems: &mut Vec<SpritesheetProblem>) -> Vec<ImportedFrame> {
+        self.0
+            .iter()
+            .map(|(name, frame)| frame.import(name, problems))
+            .collect()
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct AsepriteData {
+    frames: Frames,
+    meta: AsepriteMeta,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct AsepriteMeta {
+    image: String,
+    size: Option<JsonSize>,
+    #[serde(default)]
+    frame_tags: Vec<FrameTag>,
+}
+
+#[derive(Deserialize)]
+struct FrameTag {
+    name: String,
+    from: usize,
+    to: usize,
+    #[serde(default)]
+    direction: String,
+    /// Number of times the tag plays, as a string, absent when it repeats forever
+    repeat: Option<String>,
+}
+
+impl AsepriteData {
+    fn import(self) -> ImportedSheet {
+        let mut problems = Vec::new();
+        let frames = self.frames.import(&mut problems);
+
+        let mut animations = BTreeMap::new();
+        for tag in self.meta.frame_tags {
+            if tag.from > tag.to || tag.to >= frames.len() {
+                problems.push(SpritesheetProblem::TagOutOfRange {
+                    animation: tag.name,
+                    from: tag.from,
+                    to: tag.to,
+                });
+                continue;
+            }
+
+            let mode = match (tag.direction.as_str(), tag.repeat.as_deref()) {
+                (_, Some("1")) => PlaybackMode::Once,
+                ("reverse", _) => PlaybackMode::Reverse,
+                ("pingpong" | "pingpong_reverse", _) => PlaybackMode::PingPong,
+                _ => PlaybackMode::Loop,
+            };
+
+            animations.insert(
+                tag.name,
+                ImportedAnimation {
+                    frames: (tag.from..=tag.to).collect(),
+                    mode: Some(mode),
+                },
+            );
+        }
+
+        ImportedSheet {
+            image_path: self.meta.image,
+            image_size: self.meta.size.map(UVec2::from),
+            frames,
+            animations,
+            settings: BTreeMap::new(),
+            problems,
+        }
+    }
+}
+
+#[derive(Deserialize)]
+struct TexturePackerData {
+    frames: Frames,
+    meta: TexturePackerMeta,
+    /// Frame names keyed by animation name, written by some export templates. Animations are
+    /// inferred from frame names if absent.
+    animations: Option<BTreeMap<String, Vec<String>>>,
+}
+
+#[derive(Deserialize)]
+struct TexturePackerMeta {
+    image: String,
+    size: Option<JsonSize>,
+}
+
+impl TexturePackerData {
+    fn import(self) -> ImportedSheet {
+        let mut problems = Vec::new();
+        let frames = self.frames.import(&mut problems);
+
+        let animations = match self.animations {
+            Some(animations) => animations
+                .into_iter()
+                .map(|(animation, frame_names)| {
+                    let frames = frame_names
+                        .iter()
+                        .filter_map(|frame_name| {
+                            let position = frames.iter().position(|f| &f.name == frame_name);
+                            if position.is_none() {
+                                problems.push(SpritesheetProblem::UnknownFrame {
+                                    name: frame_name.clone(),
+                                    animation: animation.clone(),
+                                });
+                            }
+                            position
+                        })
+                        .collect();
+
+                    (animation, ImportedAnimation { frames, mode: None })
+                })
+                .collect(),
+            None => {
+                let names: Vec<&str> = frames.iter().map(|f| f.name.as_str()).collect();
+                group_by_name(&names, &mut problems)
+            }
+        };
+
+        ImportedSheet {
+            image_path: self.meta.image,
+            image_size: self.meta.size.map(UVec2::from),
+            frames,
+            animations,
+            settings: BTreeMap::new(),
+            problems,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    /// Groups `names`, returning the frames of each animation and the problems as messages.
+    fn group(names: &[&str]) -> (BTreeMap<String, Vec<usize>>, Vec<String>) {
+        let mut problems = Vec::new();
+        let animations = group_by_name(names, &mut problems)
+            .into_iter()
+            .map(|(name, animation)| (name, animation.frames))
+            .collect();
+        (
+            animations,
+            problems.iter().map(ToString::to_string).collect(),
+        )
+    }
+
+    #[test]
+    fn groups_frames_in_position_order() {
+        let (animations, problems) = group(&["walk_1", "walk_0", "idle/0", "walk_2.png"]);
+        assert!(problems.is_empty(), "{problems:?}");
+        assert_eq!(animations["walk"], [1, 0, 3]);
+        assert_eq!(animations["idle"], [2]);
+    }
+
+    #[test]
+    fn reports_duplicate_frames() {
+        let (animations, problems) = group(&["walk_0", "walk_1", "walk_1.png"]);
+        assert_eq!(
+            problems,
+            ["texture walk_1.png repeats frame 1 of animation walk"]
+        );
+        assert_eq!(animations["walk"], [0, 1]);
+    }
+
+    #[test]
+    fn reports_gaps_between_frames() {
+        let (animations, problems) = group(&["run_0", "run_3", "run_4"]);
+        assert_eq!(
+            problems,
+            ["animation run is missing frame 1 before texture run_3"]
+        );
+        assert_eq!(animations["run"], [0, 1, 2]);
+    }
+
+    #[test]
+    fn reports_badly_named_frames() {
+        let (animations, problems) = group(&["idle", "_3", "walk_x", "walk_1.5", "jump_0"]);
+        assert_eq!(
+            problems,
+            [
+                "texture idle is not named <animation>_<index>",
+                "texture _3 is not named <animation>_<index>",
+                "texture walk_x has a frame index that is not a number",
+                "texture walk_1.5 has a frame index that is not a number",
+            ]
+        );
+        assert_eq!(animations.keys().collect::<Vec<_>>(), ["jump"]);
+    }
+
+    #[test]
+    fn detects_format_from_extension() {
+        let starling = json!({ "SubTexture": [] });
+        assert_eq!(
+            SpritesheetFormat::detect(Path::new("hero.aseprite.json"), &starling),
+            SpritesheetFormat::Aseprite
+        );
+        assert_eq!(
+            SpritesheetFormat::detect(Path::new("sheets/Hero.TP.JSON"), &starling),
+            SpritesheetFormat::TexturePacker
+        );
+    }
+
+    #[test]
+    fn detects_format_from_contents() {
+        let detect = |json| SpritesheetFormat::detect(Path::new("hero.json"), &json);
+
+        assert_eq!(
+            detect(json!({ "imagePath": "hero.png", "SubTexture": [] })),
+            SpritesheetFormat::Starling
+        );
+        assert_eq!(
+            detect(json!({ "frames": {}, "meta": { "app": "https://www.aseprite.org/" } })),
+            SpritesheetFormat::Aseprite
+        );
+        assert_eq!(
+            detect(json!({ "frames": [], "meta": { "frameTags": [] } })),
+            SpritesheetFormat::Aseprite
+        );
+        assert_eq!(
+            detect(json!({
+                "frames": {},
+                "meta": { "app": "https://www.codeandweb.com/texturepacker" }
+            })),
+            SpritesheetFormat::TexturePacker
+        );
+        assert_eq!(
+            detect(json!({ "frames": [] })),
+            SpritesheetFormat::TexturePacker
+        );
+    }
+}
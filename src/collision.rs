@@ -0,0 +1,502 @@
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::physics::{PhysicalTransform, PreviousPhysicalTransform};
+
+/// Most intermediate positions tested along a sweep, bounds the cost of a long stall.
+const MAX_SWEEP_STEPS: usize = 64;
+
+/// A collider with its scale and motion over the current fixed step.
+pub(crate) type Moving<'a> = (
+    &'a Collider,
+    f32,
+    &'a PhysicalTransform,
+    &'a PreviousPhysicalTransform,
+);
+
+/// Tests two colliders along their motion this fixed step, in steps short enough that neither
+/// can pass through the other between them.
+pub(crate) fn sweep(
+    (a, a_scale, a_transform, a_previous): Moving,
+    (b, b_scale, b_transform, b_previous): Moving,
+) -> bool {
+    let travel = a_previous.distance(a_transform) + b_previous.distance(b_transform);
+    let step_length = (a.size() * a_scale).min(b.size() * b_scale).min_element() / 2.0;
+    let steps = if step_length > 0.0 {
+        ((travel / step_length).ceil() as usize).clamp(1, MAX_SWEEP_STEPS)
+    } else {
+        1
+    };
+
+    (1..=steps).any(|step| {
+        let t = step as f32 / steps as f32;
+        a.place(&a_previous.lerp(a_transform, t), a_scale)
+            .intersects(&b.place(&b_previous.lerp(b_transform, t), b_scale))
+    })
+}
+
+/// Hitbox of a sprite, in unscaled units around its center with y pointing up.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(tag = "shape", rename_all = "snake_case")]
+pub(crate) enum Collider {
+    Rect {
+        width: f32,
+        height: f32,
+    },
+    Circle {
+        radius: f32,
+    },
+    /// A vertical segment of `2 * half_length` swept by a circle of `radius`
+    Capsule {
+        radius: f32,
+        half_length: f32,
+    },
+    /// A convex polygon, corners in order
+    Polygon {
+        points: Vec<[f32; 2]>,
+    },
+}
+
+impl Collider {
+    /// Whether the shape has positive size and, for polygons, is convex.
+    pub(crate) fn is_valid(&self) -> bool {
+        match self {
+            Collider::Rect { width, height } => *width > 0.0 && *height > 0.0,
+            Collider::Circle { radius } => *radius > 0.0,
+            Collider::Capsule {
+                radius,
+                half_length,
+            } => *radius > 0.0 && *half_length >= 0.0,
+            Collider::Polygon { points } => {
+                let points: Vec<Vec2> = points.iter().copied().map(Vec2::from).collect();
+                points.len() >= 3 && is_convex(&points)
+            }
+        }
+    }
+
+    /// Size of the box around the shape.
+    pub(crate) fn size(&self) -> Vec2 {
+        match self {
+            Collider::Rect { width, height } => Vec2::new(*width, *height),
+            Collider::Circle { radius } => Vec2::splat(radius * 2.0),
+            Collider::Capsule {
+                radius,
+                half_length,
+            } => Vec2::new(radius * 2.0, (radius + half_length) * 2.0),
+            Collider::Polygon { points } => {
+                let (min, max) = points
+                    .iter()
+                    .copied()
+                    .map(Vec2::from)
+                    .fold((Vec2::MAX, Vec2::MIN), |(min, max), point| {
+                        (min.min(point), max.max(point))
+                    });
+                (max - min).max(Vec2::ZERO)
+            }
+        }
+    }
+
+    /// The shape placed at `transform`'s position and rotation, scaled by `scale`.
+    pub(crate) fn place(&self, transform: &Transform, scale: f32) -> Shape {
+        let (_, _, angle) = transform.rotation.to_euler(EulerRot::XYZ);
+        let rotation = Rot2::radians(angle);
+        let center = transform.translation.truncate();
+        let place = |point: Vec2| center + rotation * (point * scale);
+
+        match self {
+            Collider::Rect { width, height } => {
+                let half = Vec2::new(*width, *height) / 2.0;
+                Shape::Polygon(
+                    [
+                        Vec2::new(-half.x, -half.y),
+                        Vec2::new(half.x, -half.y),
+                        Vec2::new(half.x, half.y),
+                        Vec2::new(-half.x, half.y),
+                    ]
+                    .map(place)
+                    .to_vec(),
+                )
+            }
+            Collider::Circle { radius } => Shape::Circle {
+                center,
+                radius: radius * scale,
+            },
+            Collider::Capsule {
+                radius,
+                half_length,
+            } => Shape::Capsule {
+                start: place(Vec2::new(0.0, -half_length)),
+                end: place(Vec2::new(0.0, *half_length)),
+                radius: radius * scale,
+            },
+            Collider::Polygon { points } => {
+                Shape::Polygon(points.iter().copied().map(Vec2::from).map(place).collect())
+            }
+        }
+    }
+}
+
+/// A [`Collider`] placed in the world.
+#[derive(Clone, Debug)]
+pub(crate) enum Shape {
+    Polygon(Vec<Vec2>),
+    Circle { center: Vec2, radius: f32 },
+    Capsule { start: Vec2, end: Vec2, radius: f32 },
+}
+
+impl Shape {
+    pub(crate) fn intersects(&self, other: &Shape) -> bool {
+        match (self, other) {
+            (Shape::Polygon(a), Shape::Polygon(b)) => polygons_intersect(a, b),
+            (
+                Shape::Circle { center, radius },
+                Shape::Circle {
+                    center: other_center,
+                    radius: other_radius,
+                },
+            ) => center.distance(*other_center) <= radius + other_radius,
+            (
+                Shape::Capsule { start, end, radius },
+                Shape::Capsule {
+                    start: other_start,
+                    end: other_end,
+                    radius: other_radius,
+                },
+            ) => segment_distance(*start, *end, *other_start, *other_end) <= radius + other_radius,
+            (
+                Shape::Circle { center, radius },
+                Shape::Capsule {
+                    start,
+                    end,
+                    radius: capsule_radius,
+                },
+            ) => point_segment_distance(*center, *start, *end) <= radius + capsule_radius,
+            (Shape::Polygon(points), Shape::Circle { center, radius }) => {
+                polygon_segment_distance(points, *center, *center) <= *radius
+            }
+            (Shape::Polygon(points), Shape::Capsule { start, end, radius }) => {
+                polygon_segment_distance(points, *start, *end) <= *radius
+            }
+            (Shape::Capsule { .. }, Shape::Circle { .. })
+            | (Shape::Circle { .. } | Shape::Capsule { .. }, Shape::Polygon(_)) => {
+                other.intersects(self)
+            }
+        }
+    }
+}
+
+fn edges(points: &[Vec2]) -> impl Iterator<Item = (Vec2, Vec2)> + '_ {
+    points
+        .iter()
+        .copied()
+        .zip(points.iter().copied().cycle().skip(1))
+}
+
+fn is_convex(points: &[Vec2]) -> bool {
+    let crosses: Vec<f32> = edges(points)
+        .zip(edges(points).skip(1).chain(edges(points).take(1)))
+        .map(|((a, b), (_, c))| (b - a).perp_dot(c - b))
+        .collect();
+
+    crosses.iter().all(|&cross| cross >= 0.0) || crosses.iter().all(|&cross| cross <= 0.0)
+}
+
+/// Separating axis test, convex polygons overlap unless one of their edge normals separates
+/// them.
+fn polygons_intersect(a: &[Vec2], b: &[Vec2]) -> bool {
+    let project = |points: &[Vec2], axis: Vec2| {
+        points
+            .iter()
+            .map(|point| point.dot(axis))
+            .fold((f32::MAX, f32::MIN), |(min, max), d| {
+                (min.min(d), max.max(d))
+            })
+    };
+
+    edges(a).chain(edges(b)).all(|(start, end)| {
+        let axis = (end - start).perp();
+        let (a_min, a_max) = project(a, axis);
+        let (b_min, b_max) = project(b, axis);
+        a_min <= b_max && b_min <= a_max
+    })
+}
+
+fn contains(polygon: &[Vec2], point: Vec2) -> bool {
+    let sides: Vec<f32> = edges(polygon)
+        .map(|(start, end)| (end - start).perp_dot(point - start))
+        .collect();
+
+    sides.iter().all(|&side| side >= 0.0) || sides.iter().all(|&side| side <= 0.0)
+}
+
+/// Distance between a convex polygon and a segment, zero if they overlap.
+fn polygon_segment_distance(polygon: &[Vec2], start: Vec2, end: Vec2) -> f32 {
+    if contains(polygon, start) || contains(polygon, end) {
+        return 0.0;
+    }
+
+    edges(polygon)
+        .map(|(a, b)| segment_distance(a, b, start, end))
+        .fold(f32::MAX, f32::min)
+}
+
+fn point_segment_distance(point: Vec2, start: Vec2, end: Vec2) -> f32 {
+    let segment = end - start;
+    let length_squared = segment.length_squared();
+    if length_squared == 0.0 {
+        return point.distance(start);
+    }
+
+    let t = ((point - start).dot(segment) / length_squared).clamp(0.0, 1.0);
+    point.distance(start + segment * t)
+}
+
+/// Shortest distance between segments `a` and `b`, zero if they cross.
+fn segment_distance(a_start: Vec2, a_end: Vec2, b_start: Vec2, b_end: Vec2) -> f32 {
+    let a = a_end - a_start;
+    let b = b_end - b_start;
+    let denominator = a.perp_dot(b);
+
+    if denominator != 0.0 {
+        let t = (b_start - a_start).perp_dot(b) / denominator;
+        let u = (b_start - a_start).perp_dot(a) / denominator;
+        if (0.0..=1.0).contains(&t) && (0.0..=1.0).contains(&u) {
+            return 0.0;
+        }
+    }
+
+    [
+        point_segment_distance(a_start, b_start, b_end),
+        point_segment_distance(a_end, b_start, b_end),
+        point_segment_distance(b_start, a_start, a_end),
+        point_segment_distance(b_end, a_start, a_end),
+    ]
+    .into_iter()
+    .fold(f32::MAX, f32::min)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::f32::consts::SQRT_2;
+
+    use super::*;
+
+    const SQUARE: Collider = Collider::Rect {
+        width: 2.0,
+        height: 2.0,
+    };
+    const BALL: Collider = Collider::Circle { radius: 0.5 };
+    /// Vertical, from y = -1.5 to 1.5 with its caps
+    const PILL: Collider = Collider::Capsule {
+        radius: 0.5,
+        half_length: 1.0,
+    };
+
+    fn at(collider: &Collider, x: f32, y: f32, degrees: f32) -> Shape {
+        let transform = Transform::from_xyz(x, y, 0.0)
+            .with_rotation(Quat::from_rotation_z(degrees.to_radians()));
+        collider.place(&transform, 1.0)
+    }
+
+    /// Checks both argument orders agree on whether the shapes intersect.
+    fn assert_intersects(a: &Shape, b: &Shape, expected: bool) {
+        assert_eq!(a.intersects(b), expected, "{a:?} against {b:?}");
+        assert_eq!(b.intersects(a), expected, "{b:?} against {a:?}");
+    }
+
+    #[test]
+    fn polygons() {
+        let square = at(&SQUARE, 0.0, 0.0, 0.0);
+        assert_intersects(&square, &at(&SQUARE, 1.5, 0.5, 0.0), true);
+        assert_intersects(&square, &at(&SQUARE, 2.0, 0.0, 0.0), true);
+        assert_intersects(&square, &at(&SQUARE, 2.0, 2.0, 0.0), true);
+        assert_intersects(&square, &at(&SQUARE, 2.1, 0.0, 0.0), false);
+
+        // A diamond reaches √2 from its center
+        assert_intersects(&square, &at(&SQUARE, 1.0 + SQRT_2 - 0.05, 0.0, 45.0), true);
+        assert_intersects(&square, &at(&SQUARE, 1.0 + SQRT_2 + 0.05, 0.0, 45.0), false);
+    }
+
+    #[test]
+    fn polygons_separated_along_a_rotated_edge() {
+        // The boxes around the shapes overlap, only the diamond's edge normal separates them
+        let square = at(&SQUARE, 0.0, 0.0, 0.0);
+        let diamond = at(&SQUARE, 2.2, 2.2, 45.0);
+        assert_intersects(&square, &diamond, false);
+        assert_intersects(&square, &at(&SQUARE, 1.6, 1.6, 45.0), true);
+    }
+
+    #[test]
+    fn polygon_with_convex_points() {
+        let triangle = Collider::Polygon {
+            points: vec![[-1.0, -1.0], [1.0, -1.0], [0.0, 1.0]],
+        };
+        assert_intersects(
+            &at(&triangle, 0.0, 0.0, 0.0),
+            &at(&SQUARE, 0.0, 2.0, 0.0),
+            true,
+        );
+        // Flipped upside down the tip points away
+        assert_intersects(
+            &at(&triangle, 0.0, 0.0, 180.0),
+            &at(&SQUARE, 0.0, 2.05, 0.0),
+            false,
+        );
+    }
+
+    #[test]
+    fn circles() {
+        let ball = at(&BALL, 0.0, 0.0, 0.0);
+        assert_intersects(&ball, &at(&BALL, 0.5, 0.0, 0.0), true);
+        assert_intersects(&ball, &at(&BALL, 1.0, 0.0, 0.0), true);
+        assert_intersects(&ball, &at(&BALL, 0.6, 0.8, 0.0), true);
+        assert_intersects(&ball, &at(&BALL, 1.1, 0.0, 0.0), false);
+    }
+
+    #[test]
+    fn capsules() {
+        let pill = at(&PILL, 0.0, 0.0, 0.0);
+        assert_intersects(&pill, &at(&PILL, 0.5, 1.0, 0.0), true);
+        assert_intersects(&pill, &at(&PILL, 1.0, 0.0, 0.0), true);
+        assert_intersects(&pill, &at(&PILL, 1.1, 0.0, 0.0), false);
+        assert_intersects(&pill, &at(&PILL, 0.0, 3.0, 0.0), true);
+        assert_intersects(&pill, &at(&PILL, 0.0, 3.1, 0.0), false);
+
+        // Crossing without any end inside the other
+        assert_intersects(&pill, &at(&PILL, 0.0, 0.0, 90.0), true);
+        assert_intersects(&pill, &at(&PILL, 0.0, 1.95, 90.0), true);
+        assert_intersects(&pill, &at(&PILL, 0.0, 2.05, 90.0), false);
+    }
+
+    #[test]
+    fn circle_and_capsule() {
+        let pill = at(&PILL, 0.0, 0.0, 0.0);
+        assert_intersects(&pill, &at(&BALL, 1.0, 0.5, 0.0), true);
+        assert_intersects(&pill, &at(&BALL, 1.1, 0.5, 0.0), false);
+        assert_intersects(&pill, &at(&BALL, 0.0, 2.0, 0.0), true);
+        assert_intersects(&pill, &at(&BALL, 0.0, 2.1, 0.0), false);
+        assert_intersects(&pill, &at(&BALL, 0.6, 1.8, 0.0), true);
+
+        let lying = at(&PILL, 0.0, 0.0, 90.0);
+        assert_intersects(&lying, &at(&BALL, 1.95, 0.0, 0.0), true);
+        assert_intersects(&lying, &at(&BALL, 2.05, 0.0, 0.0), false);
+        assert_intersects(&lying, &at(&BALL, 0.5, 1.05, 0.0), false);
+    }
+
+    #[test]
+    fn polygon_and_circle() {
+        let square = at(&SQUARE, 0.0, 0.0, 0.0);
+        assert_intersects(&square, &at(&BALL, 0.0, 0.0, 0.0), true);
+        assert_intersects(&square, &at(&BALL, 1.5, 0.0, 0.0), true);
+        assert_intersects(&square, &at(&BALL, 1.6, 0.0, 0.0), false);
+        // Inside the box around the square but off its rounded-off corner
+        assert_intersects(&square, &at(&BALL, 1.4, 1.4, 0.0), false);
+        assert_intersects(&square, &at(&BALL, 1.3, 1.3, 0.0), true);
+
+        let diamond = at(&SQUARE, 0.0, 0.0, 45.0);
+        assert_intersects(&diamond, &at(&BALL, SQRT_2 + 0.45, 0.0, 0.0), true);
+        assert_intersects(&diamond, &at(&BALL, SQRT_2 + 0.55, 0.0, 0.0), false);
+    }
+
+    #[test]
+    fn polygon_and_capsule() {
+        let square = at(&SQUARE, 0.0, 0.0, 0.0);
+        assert_intersects(&square, &at(&PILL, 0.0, 0.0, 0.0), true);
+        assert_intersects(&square, &at(&PILL, 1.5, 0.0, 0.0), true);
+        assert_intersects(&square, &at(&PILL, 1.6, 0.0, 0.0), false);
+        assert_intersects(&square, &at(&PILL, 0.0, 2.5, 0.0), true);
+        assert_intersects(&square, &at(&PILL, 0.0, 2.6, 0.0), false);
+
+        // A long capsule through the square with both ends outside it
+        let beam = Collider::Capsule {
+            radius: 0.1,
+            half_length: 5.0,
+        };
+        assert_intersects(&square, &at(&beam, 0.0, 0.0, 90.0), true);
+        assert_intersects(&square, &at(&beam, 0.0, 0.0, 45.0), true);
+        assert_intersects(&square, &at(&beam, 0.0, 1.15, 90.0), false);
+    }
+
+    #[test]
+    fn segment_distances() {
+        let distance = |a: [f32; 4], b: [f32; 4]| {
+            segment_distance(
+                Vec2::new(a[0], a[1]),
+                Vec2::new(a[2], a[3]),
+                Vec2::new(b[0], b[1]),
+                Vec2::new(b[2], b[3]),
+            )
+        };
+
+        // Crossing, touching at an end and in a T
+        assert_eq!(distance([-1.0, 0.0, 1.0, 0.0], [0.0, -1.0, 0.0, 1.0]), 0.0);
+        assert_eq!(distance([0.0, 0.0, 1.0, 0.0], [1.0, 0.0, 1.0, 1.0]), 0.0);
+        assert_eq!(distance([0.0, 0.0, 2.0, 0.0], [1.0, 1.0, 1.0, 3.0]), 1.0);
+        // Parallel, collinear and skewed apart
+        assert_eq!(distance([0.0, 0.0, 2.0, 0.0], [0.0, 1.0, 2.0, 1.0]), 1.0);
+        assert_eq!(distance([0.0, 0.0, 1.0, 0.0], [2.0, 0.0, 3.0, 0.0]), 1.0);
+        assert_eq!(distance([0.0, 0.0, 1.0, 1.0], [3.0, 0.0, 3.0, 4.0]), 2.0);
+    }
+
+    #[test]
+    fn point_segment_distances() {
+        let start = Vec2::new(0.0, 0.0);
+        let end = Vec2::new(2.0, 0.0);
+        assert_eq!(point_segment_distance(Vec2::new(1.0, 1.0), start, end), 1.0);
+        assert_eq!(point_segment_distance(Vec2::new(5.0, 4.0), start, end), 5.0);
+        assert_eq!(
+            point_segment_distance(Vec2::new(-3.0, 4.0), start, end),
+            5.0
+        );
+        assert_eq!(point_segment_distance(Vec2::new(1.0, 0.0), start, end), 0.0);
+        // A segment of no length is a point
+        assert_eq!(
+            point_segment_distance(Vec2::new(3.0, 4.0), start, start),
+            5.0
+        );
+    }
+
+    #[test]
+    fn convexity() {
+        let points =
+            |points: &[[f32; 2]]| -> Vec<Vec2> { points.iter().copied().map(Vec2::from).collect() };
+
+        let square = points(&[[0.0, 0.0], [1.0, 0.0], [1.0, 1.0], [0.0, 1.0]]);
+        assert!(is_convex(&square));
+        let clockwise: Vec<Vec2> = square.iter().rev().copied().collect();
+        assert!(is_convex(&clockwise));
+        // A corner in the middle of an edge
+        assert!(is_convex(&points(&[
+            [0.0, 0.0],
+            [1.0, 0.0],
+            [2.0, 0.0],
+            [1.0, 1.0]
+        ])));
+        assert!(!is_convex(&points(&[
+            [0.0, 0.0],
+            [2.0, 0.0],
+            [1.0, 0.5],
+            [2.0, 2.0],
+            [0.0, 2.0]
+        ])));
+    }
+
+    #[test]
+    fn collider_validity() {
+        assert!(SQUARE.is_valid());
+        assert!(PILL.is_valid());
+        assert!(!Collider::Circle { radius: 0.0 }.is_valid());
+        assert!(
+            !Collider::Polygon {
+                points: vec![[0.0, 0.0], [1.0, 1.0]]
+            }
+            .is_valid()
+        );
+        assert!(
+            !Collider::Polygon {
+                points: vec![[0.0, 0.0], [2.0, 0.0], [1.0, 0.5], [2.0, 2.0], [0.0, 2.0]]
+            }
+            .is_valid()
+        );
+    }
+}
@@ -0,0 +1,104 @@
+use bevy::prelude::*;
+
+use crate::{
+    animation::{AnimationController, AnimationSystems, Condition, DragonBonesSkeleton, Skeleton},
+    debris::{self, Debris},
+    game::{GameState, InGameState, ScreenConstraints},
+};
+
+const SKELETON_PATH: &str = "coworker_ske.json";
+/// Same size as the player, the skeleton is built from the same body parts
+const SCALE: f32 = 0.25;
+/// Where the coworker stands, as a fraction of the screen width from the left edge
+const POSITION_X: f32 = 0.06;
+/// Horizontal distance from debris at which the coworker ducks, before screen scaling
+const DUCK_DISTANCE: f32 = 150.0;
+
+/// A coworker watching from the edge of the site, played from a DragonBones skeleton.
+pub(crate) struct CoworkerPlugin;
+
+impl Plugin for CoworkerPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<CoworkerSkeleton>()
+            .add_systems(OnEnter(GameState::InGame), spawn_coworker)
+            .add_systems(
+                Update,
+                watch_debris
+                    .before(AnimationSystems)
+                    .run_if(in_state(InGameState::Running)),
+            )
+            .add_systems(
+                Update,
+                handle_resize
+                    .run_if(in_state(GameState::InGame).and(resource_changed::<ScreenConstraints>)),
+            )
+            .add_systems(OnExit(GameState::InGame), despawn_coworker);
+    }
+}
+
+/// Keeps the coworker skeleton loaded between games.
+#[derive(Resource)]
+struct CoworkerSkeleton(Handle<DragonBonesSkeleton>);
+
+impl FromWorld for CoworkerSkeleton {
+    fn from_world(world: &mut World) -> Self {
+        CoworkerSkeleton(world.resource::<AssetServer>().load(SKELETON_PATH))
+    }
+}
+
+#[derive(Component)]
+struct Coworker;
+
+/// Stands on the ground with the skeleton's origin at the coworker's feet.
+fn coworker_transform(constraints: ScreenConstraints) -> Transform {
+    let x = constraints.min_x + POSITION_X * (constraints.max_x - constraints.min_x);
+
+    Transform::from_xyz(x, debris::ground_y(constraints), 5.0)
+        .with_scale(Vec3::splat(SCALE * constraints.scale))
+}
+
+fn spawn_coworker(
+    mut commands: Commands,
+    skeleton: Res<CoworkerSkeleton>,
+    constraints: Res<ScreenConstraints>,
+) {
+    commands.spawn((
+        Coworker,
+        Skeleton::new(skeleton.0.clone(), Some("idle")),
+        AnimationController::new("idle")
+            .with_transition("idle", "duck", [Condition::Is("ducking", true)])
+            .with_transition("duck", "idle", [Condition::Is("ducking", false)]),
+        coworker_transform(*constraints),
+    ));
+}
+
+fn watch_debris(
+    mut coworker: Query<(&Transform, &mut AnimationController), With<Coworker>>,
+    debris: Query<&Transform, With<Debris>>,
+    constraints: Res<ScreenConstraints>,
+) {
+    let Ok((transform, mut controller)) = coworker.single_mut() else {
+        return;
+    };
+
+    let reach = DUCK_DISTANCE * constraints.scale;
+    let ducking = debris
+        .iter()
+        .any(|debris| (debris.translation.x - transform.translation.x).abs() < reach);
+    controller.set_bool("ducking", ducking);
+}
+
+fn handle_resize(
+    mut coworker: Query<&mut Transform, With<Coworker>>,
+    constraints: Res<ScreenConstraints>,
+) {
+    for mut transform in coworker.iter_mut() {
+        *transform = coworker_transform(*constraints);
+    }
+}
+
+fn despawn_coworker(mut commands: Commands, coworker: Query<Entity, With<Coworker>>) {
+    for entity in coworker.iter() {
+        commands.entity(entity).despawn();
+    }
+}
@@ -1,106 +1,480 @@
-use bevy::prelude::*;
-use rand::Rng;
+use bevy::{
+    asset::{AssetLoader, LoadContext, io::Reader},
+    prelude::*,
+};
+use rand::{Rng, distr::weighted::WeightedIndex, prelude::Distribution};
 use serde::{Deserialize, Serialize};
+use std::path::Path;
+use thiserror::Error;
 
-use crate::game::{InGameState, ScreenConstraints};
+use crate::{
+    collision::Collider,
+    difficulty::PacedTime,
+    game::{GameState, InGameState, ScreenConstraints},
+    physics::{MovementSystems, PhysicalTransform, PreviousPhysicalTransform},
+};
 
 const ACCELERATION: f32 = -1000.0;
+/// Height above the bottom of the screen at which the center of debris that doesn't survive
+/// hitting the ground disappears
 const GROUND_Y_DELTA: f32 = 100.0;
+/// Height of the ground above the bottom of the screen, level with the player's feet. Debris
+/// that survives its impact rests, bounces and rolls on it.
+const REST_Y_DELTA: f32 = 25.0;
+/// Bounces slower than this end the debris' life
+const MIN_BOUNCE_SPEED: f32 = 60.0;
+/// Rolling debris slower than this is removed
+const MIN_ROLL_SPEED: f32 = 20.0;
+const LINGER_FADE_SECS: f32 = 0.5;
+/// Longest fall simulated when predicting where debris lands
+const MAX_PREDICTION_STEPS: usize = 4096;
+const DEBRIS_DATA_PATH: &str = "debris.json";
 
 pub(crate) struct DebrisPlugin;
 
 impl Plugin for DebrisPlugin {
     fn build(&self, app: &mut App) {
-        app.insert_resource::<DebrisData>(
-            serde_json::from_str(include_str!("../assets/debris.json"))
-                .expect("expected correctly formatted debris definition"),
-        )
-        .add_systems(Update, fall.run_if(in_state(InGameState::Running)))
-        .add_systems(
-            Update,
-            handle_resize
-                .run_if(in_state(InGameState::Running).and(resource_changed::<ScreenConstraints>)),
-        );
+        app.init_asset::<DebrisData>()
+            .init_asset_loader::<DebrisDataLoader>()
+            .init_resource::<DebrisDataHandle>()
+            .add_systems(Update, sync_debris_data)
+            .add_systems(
+                FixedUpdate,
+                fall.in_set(MovementSystems)
+                    .run_if(in_state(InGameState::Running)),
+            )
+            .add_systems(
+                Update,
+                handle_resize.run_if(
+                    in_state(InGameState::Running).and(resource_changed::<ScreenConstraints>),
+                ),
+            )
+            .add_systems(
+                Update,
+                apply_definition_changes.run_if(resource_exists_and_changed::<DebrisData>),
+            )
+            .add_systems(
+                Update,
+                fade_lingering.run_if(in_state(InGameState::Running)),
+            )
+            .add_systems(OnExit(GameState::InGame), despawn_debris);
+    }
+}
+
+#[derive(Error, Debug)]
+pub(crate) enum DebrisDataLoaderError {
+    #[error("could not read debris data: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("could not parse debris data: {0}")]
+    Json(#[from] serde_json::Error),
+    #[error("debris data has no definitions")]
+    Empty,
+    #[error("debris {name} has an empty or concave collider")]
+    InvalidCollider { name: String },
+    #[error("debris {name} must shatter into fragments smaller than itself")]
+    InvalidFragmentSize { name: String },
+}
+
+#[derive(Default)]
+struct DebrisDataLoader;
+
+impl AssetLoader for DebrisDataLoader {
+    type Asset = DebrisData;
+    type Settings = ();
+    type Error = DebrisDataLoaderError;
+
+    async fn load(
+        &self,
+        reader: &mut dyn Reader,
+        _settings: &(),
+        _load_context: &mut LoadContext<'_>,
+    ) -> Result<Self::Asset, Self::Error> {
+        let mut bytes = Vec::new();
+        reader.read_to_end(&mut bytes).await?;
+        let data: DebrisData = serde_json::from_slice(&bytes)?;
+
+        if data.definitions.is_empty() {
+            return Err(DebrisDataLoaderError::Empty);
+        }
+
+        if let Some(definition) = data.definitions.iter().find(|d| !d.collider.is_valid()) {
+            return Err(DebrisDataLoaderError::InvalidCollider {
+                name: definition.name.clone(),
+            });
+        }
+
+        if let Some(definition) = data.definitions.iter().find(
+            |d| matches!(d.impact, Impact::Shatter { size, .. } if !(size > 0.0 && size < 1.0)),
+        ) {
+            return Err(DebrisDataLoaderError::InvalidFragmentSize {
+                name: definition.name.clone(),
+            });
+        }
+
+        Ok(data)
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["json"]
+    }
+}
+
+/// Keeps the debris definitions loaded, and watched for changes when hot reloading.
+#[derive(Resource)]
+struct DebrisDataHandle(Handle<DebrisData>);
+
+impl FromWorld for DebrisDataHandle {
+    fn from_world(world: &mut World) -> Self {
+        DebrisDataHandle(world.resource::<AssetServer>().load(DEBRIS_DATA_PATH))
+    }
+}
+
+/// Copies the definitions into the [`DebrisData`] resource whenever they (re)load. A reload
+/// that fails to parse leaves the asset, and so the resource, as it was.
+fn sync_debris_data(
+    mut commands: Commands,
+    mut events: MessageReader<AssetEvent<DebrisData>>,
+    handle: Res<DebrisDataHandle>,
+    assets: Res<Assets<DebrisData>>,
+) {
+    for event in events.read() {
+        let (AssetEvent::Added { id } | AssetEvent::Modified { id }) = *event else {
+            continue;
+        };
+
+        if id != handle.0.id() {
+            continue;
+        }
+
+        if let Some(data) = assets.get(id) {
+            info!("Loaded {} debris definitions", data.definitions.len());
+            commands.insert_resource(data.clone());
+        }
     }
 }
 
 #[derive(Component, Default)]
 pub(crate) struct Debris {
     pub(crate) definition_idx: usize,
-    velocity: f32,
+    physics: DebrisPhysics,
+    impact: Impact,
+    /// Scale relative to the definition, smaller for fragments
+    size: f32,
+    /// Unscaled, y pointing up
+    velocity: Vec2,
+    /// Radians per second, counterclockwise
+    angular_velocity: f32,
+    /// Whether the debris has come to rest on the ground
+    grounded: bool,
+    bounces: u32,
+    /// Seconds left on the ground, for lingering debris
+    linger_remaining: Option<f32>,
 }
 
 impl Debris {
-    pub(crate) fn new(
+    fn new(
         definition_idx: usize,
         definition: &DebrisDefinition,
         asset_server: &AssetServer,
         constraints: ScreenConstraints,
         transform: Transform,
+        size: f32,
+        velocity: Vec2,
     ) -> impl Bundle + use<> {
+        let transform =
+            transform.with_scale(Vec3::splat(definition.scale * size * constraints.scale));
         (
             Debris {
                 definition_idx,
-                velocity: 0.0,
+                physics: definition.physics,
+                impact: definition.impact.clone(),
+                size,
+                velocity,
+                angular_velocity: definition.physics.angular_velocity(),
+                grounded: false,
+                bounces: 0,
+                linger_remaining: None,
             },
             Sprite::from_image(asset_server.load(definition.sprite_path.clone())),
-            transform.with_scale(Vec3::splat(definition.scale * constraints.scale)),
-            // Uncomment to visualize collision rectangles
+            transform,
+            PhysicalTransform(transform),
+            // Uncomment to visualize the collider's bounds
             // children![(
             //     Sprite::from_color(
             //         Color::srgba(1.0, 1.0, 1.0, 0.3),
-            //         Vec2::new(definition.coll_width, definition.coll_height)
+            //         definition.collider.size()
             //     ),
             //     Transform::default().with_scale(Vec3::splat(1.0 / definition.scale))
             // )],
         )
     }
 
-    pub(crate) fn new_random(
+    /// Scale of the collider relative to the definition's.
+    pub(crate) fn size(&self) -> f32 {
+        self.size
+    }
+}
+
+/// Debris picked to spawn, decided ahead of time so where it lands can be telegraphed.
+#[derive(Clone, Copy, Debug)]
+pub(crate) struct DebrisSpawn {
+    pub(crate) definition_idx: usize,
+    pub(crate) x: f32,
+    velocity: Vec2,
+}
+
+impl DebrisSpawn {
+    pub(crate) fn new(data: &DebrisData, definition_idx: usize, x: f32) -> Option<Self> {
+        Some(DebrisSpawn {
+            definition_idx,
+            x,
+            velocity: data
+                .definitions
+                .get(definition_idx)?
+                .physics
+                .initial_velocity(),
+        })
+    }
+
+    /// `None` if the definition went away since the spawn was picked.
+    pub(crate) fn bundle(
+        &self,
         data: &DebrisData,
-        constraints: ScreenConstraints,
         asset_server: &AssetServer,
-    ) -> impl Bundle {
-        let idx = rand::rng().random_range(0..data.definitions.len());
-        let x = rand::rng().random_range(constraints.min_x..constraints.max_x);
-        Debris::new(
-            idx,
-            &data.definitions[idx],
+        constraints: ScreenConstraints,
+    ) -> Option<impl Bundle + use<>> {
+        let definition = data.definitions.get(self.definition_idx)?;
+        Some(Debris::new(
+            self.definition_idx,
+            definition,
             asset_server,
             constraints,
-            Transform::from_translation(Vec3::new(
-                x as f32,
-                constraints.max_y + (50. * constraints.scale),
-                1.,
-            )),
-        )
+            Transform::from_translation(Vec3::new(self.x, spawn_y(constraints), 1.)),
+            1.0,
+            self.velocity,
+        ))
+    }
+
+    /// Where the debris will first touch the ground, found by running the same simulation as
+    /// [`fall`] with the steps `time` currently takes.
+    pub(crate) fn landing_x(
+        &self,
+        data: &DebrisData,
+        constraints: ScreenConstraints,
+        time: &PacedTime,
+    ) -> Option<f32> {
+        let timestep = time.fall_delta_secs();
+        let definition = data.definitions.get(self.definition_idx)?;
+        let impact_y = impact_y(
+            &definition.impact,
+            definition.collider.size().y / 2.0,
+            constraints,
+        );
+        let mut position = Vec2::new(self.x, spawn_y(constraints));
+        let mut velocity = self.velocity;
+
+        for _ in 0..MAX_PREDICTION_STEPS {
+            position += velocity * constraints.scale * timestep;
+            definition
+                .physics
+                .accelerate(&mut velocity, timestep, time.gravity_scale());
+
+            if (position.x < constraints.min_x && velocity.x < 0.0)
+                || (position.x > constraints.max_x && velocity.x > 0.0)
+            {
+                velocity.x = -velocity.x;
+            }
+
+            if position.y <= impact_y && velocity.y <= 0.0 {
+                break;
+            }
+        }
+
+        Some(position.x)
+    }
+}
+
+/// Height of the ground debris rests on, see [`REST_Y_DELTA`].
+pub(crate) fn ground_y(constraints: ScreenConstraints) -> f32 {
+    constraints.min_y + (REST_Y_DELTA * constraints.scale)
+}
+
+/// Height of the center of debris `half_height` tall when it hits the ground.
+fn impact_y(impact: &Impact, half_height: f32, constraints: ScreenConstraints) -> f32 {
+    match impact {
+        Impact::Despawn => constraints.min_y + (GROUND_Y_DELTA * constraints.scale),
+        _ => ground_y(constraints) + half_height * constraints.scale,
     }
 }
 
+fn spawn_y(constraints: ScreenConstraints) -> f32 {
+    constraints.max_y + (50. * constraints.scale)
+}
+
 fn fall(
     mut commands: Commands,
-    mut debris: Query<(&mut Transform, &mut Debris, Entity)>,
-    time: Res<Time>,
+    mut debris: Query<(&mut PhysicalTransform, &mut Debris, Entity)>,
+    debris_data: Option<Res<DebrisData>>,
+    asset_server: Res<AssetServer>,
+    time: PacedTime,
     constraints: Res<ScreenConstraints>,
 ) {
-    let ground_y = constraints.min_y + (GROUND_Y_DELTA * constraints.scale);
+    let ground_y = ground_y(*constraints);
+    let delta = time.fall_delta_secs();
 
     for (mut transform, mut debris, entity) in debris.iter_mut() {
-        if transform.translation.y <= ground_y {
-            commands.entity(entity).despawn();
+        let Some(definition) = debris_data
+            .as_ref()
+            .and_then(|data| data.definitions.get(debris.definition_idx))
+        else {
             continue;
+        };
+
+        let translation = debris.velocity * constraints.scale * delta;
+
+        if !debris.grounded {
+            let physics = debris.physics;
+            physics.accelerate(&mut debris.velocity, delta, time.gravity_scale());
         }
 
-        let translation = debris.velocity * time.delta_secs();
-        debris.velocity += ACCELERATION * constraints.scale * time.delta_secs();
-        transform.translation.y += translation
+        transform.translation.x += translation.x;
+        transform.translation.y += translation.y;
+        transform.rotate_z(debris.angular_velocity * delta);
+
+        // Resting height of the debris' center, its rotation is not taken into account
+        let half_height = definition.collider.size().y / 2.0 * debris.size;
+        let rest_y = ground_y + half_height * constraints.scale;
+
+        if !debris.grounded {
+            let impact = if debris.size < 1.0 {
+                // Fragments don't shatter again
+                Impact::Despawn
+            } else {
+                debris.impact.clone()
+            };
+
+            // Bounce off the sides of the screen instead of drifting out of reach
+            if (transform.translation.x < constraints.min_x && debris.velocity.x < 0.0)
+                || (transform.translation.x > constraints.max_x && debris.velocity.x > 0.0)
+            {
+                debris.velocity.x = -debris.velocity.x;
+            }
+
+            if transform.translation.y > impact_y(&impact, half_height, *constraints)
+                || debris.velocity.y > 0.0
+            {
+                continue;
+            }
+
+            transform.translation.y = rest_y;
+
+            match impact {
+                Impact::Despawn => {
+                    commands.entity(entity).despawn();
+                }
+                Impact::Bounce {
+                    restitution,
+                    max_bounces,
+                } => {
+                    debris.velocity.y *= -restitution;
+                    debris.bounces += 1;
+                    if debris.bounces > max_bounces || debris.velocity.y < MIN_BOUNCE_SPEED {
+                        commands.entity(entity).despawn();
+                    }
+                }
+                Impact::Roll { speed, .. } => {
+                    debris.grounded = true;
+                    debris.velocity.y = 0.0;
+                    if debris.velocity.x.abs() < speed {
+                        let direction = if debris.velocity.x == 0.0 {
+                            random_direction()
+                        } else {
+                            debris.velocity.x.signum()
+                        };
+                        debris.velocity.x = speed * direction;
+                    }
+                }
+                Impact::Shatter { count, size, speed } => {
+                    commands.entity(entity).despawn();
+                    for _ in 0..count {
+                        let velocity = Vec2::new(
+                            rand::rng().random_range(-speed..=speed),
+                            speed * rand::rng().random_range(0.5..=1.0),
+                        );
+                        commands.spawn(Debris::new(
+                            debris.definition_idx,
+                            definition,
+                            &asset_server,
+                            *constraints,
+                            Transform::from_translation(transform.translation),
+                            size,
+                            velocity,
+                        ));
+                    }
+                }
+                Impact::Linger { duration_secs } => {
+                    debris.grounded = true;
+                    debris.velocity = Vec2::ZERO;
+                    debris.angular_velocity = 0.0;
+                    debris.linger_remaining = Some(duration_secs);
+                }
+            }
+
+            continue;
+        }
+
+        if let Some(remaining) = &mut debris.linger_remaining {
+            // Lingering lasts as long however fast debris falls
+            *remaining -= time.delta().as_secs_f32();
+            if *remaining <= 0.0 {
+                commands.entity(entity).despawn();
+            }
+            continue;
+        }
+
+        if let Impact::Roll { friction, .. } = debris.impact {
+            debris.velocity.x *= (1.0 - friction * delta).max(0.0);
+            // Turn as fast as a wheel of the debris' height would
+            debris.angular_velocity = -debris.velocity.x / half_height.max(f32::EPSILON);
+
+            let margin = half_height * 2.0 * constraints.scale;
+            if debris.velocity.x.abs() < MIN_ROLL_SPEED
+                || transform.translation.x < constraints.min_x - margin
+                || transform.translation.x > constraints.max_x + margin
+            {
+                commands.entity(entity).despawn();
+            }
+        }
+    }
+}
+
+/// Fragments aren't spawned by the level, so they are cleaned up here.
+fn despawn_debris(mut commands: Commands, debris: Query<Entity, With<Debris>>) {
+    for entity in debris.iter() {
+        commands.entity(entity).despawn();
+    }
+}
+
+/// Fades lingering debris out over its last moments on the ground.
+fn fade_lingering(mut debris: Query<(&mut Sprite, &Debris)>) {
+    for (mut sprite, debris) in debris.iter_mut() {
+        if let Some(remaining) = debris.linger_remaining {
+            sprite
+                .color
+                .set_alpha((remaining / LINGER_FADE_SECS).clamp(0.0, 1.0));
+        }
     }
 }
 
 fn handle_resize(
     constraints: Res<ScreenConstraints>,
-    debris_data: Res<DebrisData>,
-    mut debris: Query<(&mut Transform, &Debris)>,
+    debris_data: Option<Res<DebrisData>>,
+    mut debris: Query<(
+        &mut Transform,
+        &mut PhysicalTransform,
+        &mut PreviousPhysicalTransform,
+        &Debris,
+    )>,
     mut previous_scale: Local<Option<f32>>,
 ) {
     let scale_ratio = match *previous_scale {
@@ -108,26 +482,217 @@ fn handle_resize(
         None => constraints.scale,
     };
 
-    for (mut transform, debris_el) in debris.iter_mut() {
-        let def = &debris_data.definitions[debris_el.definition_idx];
-        transform.scale = Vec3::splat(constraints.scale * def.scale);
-        transform.translation.y *= scale_ratio;
-        transform.translation.x *= scale_ratio;
+    for (mut transform, mut physical, mut previous, debris_el) in debris.iter_mut() {
+        if let Some(def) = debris_data
+            .as_ref()
+            .and_then(|data| data.definitions.get(debris_el.definition_idx))
+        {
+            transform.scale = Vec3::splat(constraints.scale * def.scale * debris_el.size);
+        }
+        physical.translation.y *= scale_ratio;
+        physical.translation.x *= scale_ratio;
+        previous.reset();
     }
 
     *previous_scale = Some(constraints.scale);
 }
 
-#[derive(Serialize, Deserialize, Resource)]
+/// Applies tuned definitions to debris that is already falling. Debris whose definition was
+/// removed is despawned.
+fn apply_definition_changes(
+    mut commands: Commands,
+    debris_data: Res<DebrisData>,
+    asset_server: Res<AssetServer>,
+    constraints: Res<ScreenConstraints>,
+    mut debris: Query<(&mut Transform, &mut Sprite, &mut Debris, Entity)>,
+) {
+    for (mut transform, mut sprite, mut debris_el, entity) in debris.iter_mut() {
+        let Some(def) = debris_data.definitions.get(debris_el.definition_idx) else {
+            commands.entity(entity).despawn();
+            continue;
+        };
+
+        debris_el.physics = def.physics;
+        debris_el.impact = def.impact.clone();
+        transform.scale = Vec3::splat(constraints.scale * def.scale * debris_el.size);
+        if sprite
+            .image
+            .path()
+            .is_none_or(|path| path.path() != Path::new(&def.sprite_path))
+        {
+            sprite.image = asset_server.load(def.sprite_path.clone());
+        }
+    }
+}
+
+#[derive(Asset, TypePath, Serialize, Deserialize, Resource, Clone)]
 pub(crate) struct DebrisData {
     pub(crate) definitions: Vec<DebrisDefinition>,
 }
 
-#[derive(Serialize, Deserialize)]
+impl DebrisData {
+    pub(crate) fn find(&self, name: &str) -> Option<usize> {
+        self.definitions.iter().position(|d| d.name == name)
+    }
+
+    /// Whether the definition at `idx` is in `pool` (any if empty) and allowed `elapsed_secs`
+    /// into the run, given how many of each definition are already falling.
+    pub(crate) fn allows(
+        &self,
+        idx: usize,
+        elapsed_secs: f32,
+        counts: &[usize],
+        pool: &[String],
+    ) -> bool {
+        let Some(definition) = self.definitions.get(idx) else {
+            return false;
+        };
+
+        let count = counts.get(idx).copied().unwrap_or_default();
+        (pool.is_empty() || pool.contains(&definition.name))
+            && definition.can_spawn(elapsed_secs, count)
+    }
+
+    /// Picks a definition by weight among those it [allows](Self::allows). `None` if none is
+    /// allowed.
+    pub(crate) fn pick(
+        &self,
+        elapsed_secs: f32,
+        counts: &[usize],
+        pool: &[String],
+    ) -> Option<usize> {
+        let weights = self
+            .definitions
+            .iter()
+            .enumerate()
+            .map(|(idx, definition)| {
+                if self.allows(idx, elapsed_secs, counts, pool) {
+                    definition.spawn_weight
+                } else {
+                    0.0
+                }
+            });
+
+        Some(WeightedIndex::new(weights).ok()?.sample(&mut rand::rng()))
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone)]
 pub(crate) struct DebrisDefinition {
     pub(crate) name: String,
     pub(crate) sprite_path: String,
     pub(crate) scale: f32,
-    pub(crate) coll_width: f32,
-    pub(crate) coll_height: f32,
+    pub(crate) collider: Collider,
+    #[serde(flatten)]
+    pub(crate) physics: DebrisPhysics,
+    #[serde(default)]
+    pub(crate) impact: Impact,
+    /// Relative chance of being picked, zero for debris that only appears as fragments
+    #[serde(default = "default_spawn_weight")]
+    pub(crate) spawn_weight: f32,
+    /// How far into a run, in seconds, the debris starts appearing
+    #[serde(default)]
+    pub(crate) min_elapsed_secs: f32,
+    /// Most of this debris falling at once
+    #[serde(default)]
+    pub(crate) max_concurrent: Option<usize>,
+}
+
+fn default_spawn_weight() -> f32 {
+    1.0
+}
+
+impl DebrisDefinition {
+    fn can_spawn(&self, elapsed_secs: f32, count: usize) -> bool {
+        self.spawn_weight > 0.0
+            && elapsed_secs >= self.min_elapsed_secs
+            && self.max_concurrent.is_none_or(|max| count < max)
+    }
+}
+
+/// What debris does when it reaches the ground.
+#[derive(Serialize, Deserialize, Clone, Debug, Default)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub(crate) enum Impact {
+    #[default]
+    Despawn,
+    /// Bounces back up, losing speed by `restitution` each time
+    Bounce { restitution: f32, max_bounces: u32 },
+    /// Rolls along the ground at least at `speed`, slowing down by `friction` per second
+    Roll { speed: f32, friction: f32 },
+    /// Breaks into `count` copies of `size` thrown up to `speed` away
+    Shatter { count: u32, size: f32, speed: f32 },
+    /// Stays on the ground as a hazard
+    Linger { duration_secs: f32 },
+}
+
+/// How a kind of debris falls, in unscaled units per second.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug)]
+#[serde(default)]
+pub(crate) struct DebrisPhysics {
+    /// Multiplier of the shared gravity
+    pub(crate) gravity_scale: f32,
+    /// Fastest downward speed, unlimited if absent
+    pub(crate) terminal_velocity: Option<f32>,
+    /// Fraction of the velocity lost per second to air resistance
+    pub(crate) drag: f32,
+    /// Sideways speed at spawn, in a random direction
+    pub(crate) initial_velocity_x: f32,
+    /// Downward speed at spawn
+    pub(crate) initial_velocity_y: f32,
+    /// Range the spin is picked from, in degrees per second, negative spins clockwise
+    pub(crate) min_angular_velocity: f32,
+    pub(crate) max_angular_velocity: f32,
+}
+
+impl Default for DebrisPhysics {
+    fn default() -> Self {
+        DebrisPhysics {
+            gravity_scale: 1.0,
+            terminal_velocity: None,
+            drag: 0.0,
+            initial_velocity_x: 0.0,
+            initial_velocity_y: 0.0,
+            min_angular_velocity: 0.0,
+            max_angular_velocity: 0.0,
+        }
+    }
+}
+
+fn random_direction() -> f32 {
+    if rand::rng().random_bool(0.5) {
+        1.0
+    } else {
+        -1.0
+    }
+}
+
+impl DebrisPhysics {
+    fn initial_velocity(&self) -> Vec2 {
+        Vec2::new(
+            self.initial_velocity_x * random_direction(),
+            -self.initial_velocity_y,
+        )
+    }
+
+    /// Applies gravity, scaled further by `gravity_scale`, drag and the speed limit for `delta`
+    /// seconds.
+    fn accelerate(&self, velocity: &mut Vec2, delta: f32, gravity_scale: f32) {
+        velocity.y += ACCELERATION * self.gravity_scale * gravity_scale * delta;
+        *velocity *= (1.0 - self.drag * delta).max(0.0);
+        if let Some(terminal_velocity) = self.terminal_velocity {
+            velocity.y = velocity.y.max(-terminal_velocity);
+        }
+    }
+
+    fn angular_velocity(&self) -> f32 {
+        let (min, max) = (self.min_angular_velocity, self.max_angular_velocity);
+        let degrees = if min < max {
+            rand::rng().random_range(min..=max)
+        } else {
+            min
+        };
+
+        degrees.to_radians()
+    }
 }
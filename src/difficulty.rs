@@ -0,0 +1,294 @@
+use std::time::Duration;
+
+use bevy::{
+    asset::{AssetLoader, LoadContext, io::Reader},
+    ecs::system::SystemParam,
+    prelude::*,
+};
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+use crate::{game::InGameState, level::ScoreStopwatch, preset::Preset};
+
+pub(crate) struct DifficultyPlugin;
+
+impl Plugin for DifficultyPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_asset::<DifficultyCurve>()
+            .init_asset_loader::<DifficultyCurveLoader>()
+            .init_resource::<Difficulty>()
+            .add_systems(
+                FixedPreUpdate,
+                update_difficulty.run_if(in_state(InGameState::Running)),
+            );
+    }
+}
+
+#[derive(Error, Debug)]
+pub(crate) enum DifficultyCurveLoaderError {
+    #[error("could not read difficulty curve: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("could not parse difficulty curve: {0}")]
+    Json(#[from] serde_json::Error),
+    #[error("difficulty curve has no points")]
+    Empty,
+    #[error("difficulty point {0} comes before the previous one")]
+    Unsorted(usize),
+    #[error("difficulty point {0} has a spawn interval, fall speed or telegraph time out of range")]
+    InvalidPoint(usize),
+}
+
+#[derive(Default)]
+struct DifficultyCurveLoader;
+
+impl AssetLoader for DifficultyCurveLoader {
+    type Asset = DifficultyCurve;
+    type Settings = ();
+    type Error = DifficultyCurveLoaderError;
+
+    async fn load(
+        &self,
+        reader: &mut dyn Reader,
+        _settings: &(),
+        _load_context: &mut LoadContext<'_>,
+    ) -> Result<Self::Asset, Self::Error> {
+        let mut bytes = Vec::new();
+        reader.read_to_end(&mut bytes).await?;
+        let curve: DifficultyCurve = serde_json::from_slice(&bytes)?;
+
+        if curve.points.is_empty() {
+            return Err(DifficultyCurveLoaderError::Empty);
+        }
+
+        if let Some(idx) = (1..curve.points.len())
+            .find(|&i| curve.points[i].elapsed_secs < curve.points[i - 1].elapsed_secs)
+        {
+            return Err(DifficultyCurveLoaderError::Unsorted(idx));
+        }
+
+        if let Some(idx) = curve.points.iter().position(|p| {
+            !(p.spawn_interval > 0.0
+                && p.fall_speed > 0.0
+                && (0.0..f32::INFINITY).contains(&p.telegraph_secs))
+        }) {
+            return Err(DifficultyCurveLoaderError::InvalidPoint(idx));
+        }
+
+        Ok(curve)
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["json"]
+    }
+}
+
+/// How the game gets harder over a run, linearly interpolated between points and flat past
+/// either end.
+#[derive(Asset, TypePath, Serialize, Deserialize, Clone)]
+pub(crate) struct DifficultyCurve {
+    pub(crate) points: Vec<DifficultyPoint>,
+}
+
+impl DifficultyCurve {
+    pub(crate) fn at(&self, elapsed_secs: f32) -> DifficultyPoint {
+        let next = self
+            .points
+            .iter()
+            .position(|p| p.elapsed_secs > elapsed_secs)
+            .unwrap_or(self.points.len());
+
+        match (next.checked_sub(1), self.points.get(next)) {
+            (Some(prev), Some(next)) => {
+                let prev = self.points[prev];
+                let t =
+                    (elapsed_secs - prev.elapsed_secs) / (next.elapsed_secs - prev.elapsed_secs);
+                DifficultyPoint {
+                    elapsed_secs,
+                    spawn_interval: prev.spawn_interval.lerp(next.spawn_interval, t),
+                    fall_speed: prev.fall_speed.lerp(next.fall_speed, t),
+                    max_debris: (prev.max_debris as f32)
+                        .lerp(next.max_debris as f32, t)
+                        .round() as u32,
+                    telegraph_secs: prev.telegraph_secs.lerp(next.telegraph_secs, t),
+                }
+            }
+            (Some(last), None) => self.points[last],
+            (None, _) => self.points.first().copied().unwrap_or_default(),
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug)]
+pub(crate) struct DifficultyPoint {
+    /// Seconds into the run
+    pub(crate) elapsed_secs: f32,
+    /// Multiplies the waves' intervals and the delays within their patterns
+    pub(crate) spawn_interval: f32,
+    /// Multiplies how fast debris moves
+    pub(crate) fall_speed: f32,
+    /// Most debris on screen or telegraphed at once, further spawns wait until some is gone
+    pub(crate) max_debris: u32,
+    /// How long debris is telegraphed before it appears, zero to drop it without warning
+    #[serde(default = "default_telegraph_secs")]
+    pub(crate) telegraph_secs: f32,
+}
+
+fn default_telegraph_secs() -> f32 {
+    0.6
+}
+
+impl Default for DifficultyPoint {
+    fn default() -> Self {
+        DifficultyPoint {
+            elapsed_secs: 0.0,
+            spawn_interval: 1.0,
+            fall_speed: 1.0,
+            max_debris: 8,
+            telegraph_secs: default_telegraph_secs(),
+        }
+    }
+}
+
+/// The point on the current level's [`DifficultyCurve`] the run is at. Flat at the default
+/// point until the curve loads.
+#[derive(Resource, Default, Deref)]
+pub(crate) struct Difficulty {
+    #[deref]
+    current: DifficultyPoint,
+    curve: Handle<DifficultyCurve>,
+}
+
+impl Difficulty {
+    /// Starts over on `curve` for a new run, from the next step.
+    pub(crate) fn reset(&mut self, curve: Handle<DifficultyCurve>) {
+        self.current = DifficultyPoint::default();
+        self.curve = curve;
+    }
+}
+
+/// Follows the curve as it is, so changes to it apply right away when hot reloading.
+fn update_difficulty(
+    mut difficulty: ResMut<Difficulty>,
+    curves: Res<Assets<DifficultyCurve>>,
+    score_stopwatch: Res<ScoreStopwatch>,
+) {
+    if let Some(curve) = curves.get(&difficulty.curve) {
+        difficulty.current = curve.at(score_stopwatch.elapsed_secs());
+    }
+}
+
+/// Fixed time paced by the current [`Difficulty`] and the selected [`Preset`].
+#[derive(SystemParam)]
+pub(crate) struct PacedTime<'w> {
+    time: Res<'w, Time>,
+    difficulty: Res<'w, Difficulty>,
+    preset: Res<'w, Preset>,
+}
+
+impl PacedTime<'_> {
+    pub(crate) fn delta(&self) -> Duration {
+        self.time.delta()
+    }
+
+    /// Time passing for spawn patterns, faster with shorter spawn intervals.
+    pub(crate) fn spawn_delta(&self) -> Duration {
+        self.time
+            .delta()
+            .mul_f32(self.preset.tuning().spawn_rate / self.difficulty.spawn_interval)
+    }
+
+    /// Time passing for falling debris.
+    pub(crate) fn fall_delta_secs(&self) -> f32 {
+        self.time.delta_secs() * self.difficulty.fall_speed
+    }
+
+    pub(crate) fn gravity_scale(&self) -> f32 {
+        self.preset.tuning().gravity
+    }
+
+    pub(crate) fn max_debris(&self) -> usize {
+        self.difficulty.max_debris as usize
+    }
+
+    /// How long before debris appears it is telegraphed.
+    pub(crate) fn telegraph_lead(&self) -> Duration {
+        Duration::try_from_secs_f32(self.difficulty.telegraph_secs).unwrap_or(Duration::MAX)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    fn curve() -> DifficultyCurve {
+        serde_json::from_value(json!({
+            "points": [
+                {
+                    "elapsed_secs": 10.0,
+                    "spawn_interval": 1.0,
+                    "fall_speed": 1.0,
+                    "max_debris": 4,
+                    "telegraph_secs": 1.0
+                },
+                {
+                    "elapsed_secs": 20.0,
+                    "spawn_interval": 0.5,
+                    "fall_speed": 2.0,
+                    "max_debris": 7,
+                    "telegraph_secs": 0.0
+                }
+            ]
+        }))
+        .unwrap()
+    }
+
+    /// Checks every paced value of `point`, in the order spawn interval, fall speed, max debris
+    /// and telegraph time.
+    fn assert_point(point: DifficultyPoint, expected: (f32, f32, u32, f32)) {
+        let actual = (
+            point.spawn_interval,
+            point.fall_speed,
+            point.max_debris,
+            point.telegraph_secs,
+        );
+        assert!(
+            (actual.0 - expected.0).abs() < 1e-5
+                && (actual.1 - expected.1).abs() < 1e-5
+                && actual.2 == expected.2
+                && (actual.3 - expected.3).abs() < 1e-5,
+            "{actual:?} != {expected:?}"
+        );
+    }
+
+    #[test]
+    fn interpolates_between_points() {
+        let curve = curve();
+        assert_point(curve.at(10.0), (1.0, 1.0, 4, 1.0));
+        assert_point(curve.at(15.0), (0.75, 1.5, 6, 0.5));
+        assert_point(curve.at(12.5), (0.875, 1.25, 5, 0.75));
+        assert_eq!(curve.at(15.0).elapsed_secs, 15.0);
+    }
+
+    #[test]
+    fn stays_flat_past_either_end() {
+        let curve = curve();
+        assert_point(curve.at(0.0), (1.0, 1.0, 4, 1.0));
+        assert_point(curve.at(20.0), (0.5, 2.0, 7, 0.0));
+        assert_point(curve.at(1000.0), (0.5, 2.0, 7, 0.0));
+    }
+
+    #[test]
+    fn single_point_is_flat() {
+        let curve = DifficultyCurve {
+            points: vec![DifficultyPoint {
+                elapsed_secs: 5.0,
+                max_debris: 3,
+                ..default()
+            }],
+        };
+        assert_point(curve.at(0.0), (1.0, 1.0, 3, 0.6));
+        assert_point(curve.at(60.0), (1.0, 1.0, 3, 0.6));
+    }
+}
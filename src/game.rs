@@ -1,6 +1,8 @@
 use crate::{
-    animation::AnimationPlugin, debris::DebrisPlugin, level::LevelPlugin, menu::MenuPlugin,
-    player::PlayerPlugin,
+    animation::AnimationPlugin, coworker::CoworkerPlugin, debris::DebrisPlugin,
+    difficulty::DifficultyPlugin, level::LevelPlugin, menu::MenuPlugin, pause::PausePlugin,
+    physics::PhysicsPlugin, player::PlayerPlugin, save::SavePlugin, telegraph::TelegraphPlugin,
+    waves::WavesPlugin,
 };
 use bevy::{
     prelude::*,
@@ -47,7 +49,14 @@ impl Plugin for GamePlugin {
             LevelPlugin,
             AnimationPlugin,
             PlayerPlugin,
+            CoworkerPlugin,
             DebrisPlugin,
+            PhysicsPlugin,
+            TelegraphPlugin,
+            WavesPlugin,
+            DifficultyPlugin,
+            SavePlugin,
+            PausePlugin,
         ))
         .init_resource::<ScreenConstraints>()
         .init_state::<GameState>()
@@ -62,6 +71,9 @@ pub(crate) enum GameState {
     #[default]
     Menu,
     InGame,
+    /// Passed through to start the level over, as setting [`GameState::InGame`] while already
+    /// in it doesn't run its `OnExit` and `OnEnter` schedules
+    Restarting,
 }
 
 #[derive(Default, SubStates, Debug, Clone, PartialEq, Eq, Hash)]
@@ -69,7 +81,11 @@ pub(crate) enum GameState {
 pub(crate) enum InGameState {
     #[default]
     Running,
+    /// The simulation is frozen behind the pause menu
+    Paused,
     GameOver,
+    /// The level's goal was reached
+    LevelComplete,
 }
 
 #[derive(Resource, Copy, Clone)]
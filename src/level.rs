@@ -1,42 +1,58 @@
-use std::time::Duration;
-
 use bevy::{
     prelude::*,
     time::Stopwatch,
     window::{PrimaryWindow, WindowResized},
 };
 
-const STARTING_DEBRIS_TIMER_SECS: u64 = 1;
-
 use crate::{
-    animation::AnimationTextureAtlasLayout,
+    collision,
     debris::{Debris, DebrisData},
+    difficulty::Difficulty,
     game::{GameState, InGameState, ScreenConstraints},
     menu::MENU_BG_COLOR,
-    player::{COLL_HEIGHT, COLL_WIDTH, Player},
+    physics::{MovementSystems, PhysicalTransform, PreviousPhysicalTransform},
+    player::{self, Player, PlayerSpritesheet},
+    preset::{DifficultyPreset, Preset},
+    save::{Highscores, Progress},
 };
 
+mod campaign;
+mod definition;
+
+pub(crate) use campaign::{Campaign, CampaignHandle, level_id};
+pub(crate) use definition::{CurrentLevel, LevelDefinition, LoadedLevel};
+
 pub(crate) struct LevelPlugin;
 
 impl Plugin for LevelPlugin {
     fn build(&self, app: &mut App) {
-        app.add_systems(OnEnter(GameState::InGame), setup_level)
+        app.init_asset::<LevelDefinition>()
+            .init_asset_loader::<definition::LevelLoader>()
+            .init_asset::<Campaign>()
+            .init_asset_loader::<campaign::CampaignLoader>()
+            .init_resource::<CampaignHandle>()
+            .init_resource::<CurrentLevel>()
+            .add_systems(OnEnter(GameState::InGame), setup_level)
             .add_systems(OnEnter(InGameState::GameOver), show_gameover_screen)
             .add_systems(
-                Update,
-                (spawn_debris, check_collision, update_score)
+                OnEnter(InGameState::LevelComplete),
+                show_level_complete_screen,
+            )
+            .add_systems(
+                FixedUpdate,
+                (
+                    check_collision.after(MovementSystems),
+                    (tick_score, check_goal.after(check_collision)).chain(),
+                )
                     .run_if(in_state(InGameState::Running)),
             )
+            .add_systems(Update, update_score.run_if(in_state(InGameState::Running)))
             .add_systems(
                 Update,
                 (handle_escape, handle_resize).run_if(in_state(GameState::InGame)),
             )
             .add_systems(OnExit(GameState::InGame), teardown_level)
-            .init_resource::<ScoreStopwatch>()
-            .insert_resource(DebrisTimer(Timer::new(
-                Duration::from_secs(STARTING_DEBRIS_TIMER_SECS),
-                TimerMode::Repeating,
-            )));
+            .init_resource::<ScoreStopwatch>();
     }
 }
 
@@ -50,56 +66,75 @@ struct LevelEntity;
 struct ScoreText;
 
 #[derive(Resource, Deref, DerefMut, Default)]
-struct ScoreStopwatch(Stopwatch);
-
-#[derive(Resource, Deref, DerefMut)]
-struct DebrisTimer(Timer);
+pub(crate) struct ScoreStopwatch(Stopwatch);
 
 fn setup_level(
     mut commands: Commands,
-    asset_server: Res<AssetServer>,
-    mut layouts: ResMut<Assets<TextureAtlasLayout>>,
-    mut animation_layouts: ResMut<Assets<AnimationTextureAtlasLayout>>,
+    level: LoadedLevel,
+    player_spritesheet: Res<PlayerSpritesheet>,
     window: Query<&Window, With<PrimaryWindow>>,
     mut score_stopwatch: ResMut<ScoreStopwatch>,
+    mut difficulty: ResMut<Difficulty>,
     constraints: Res<ScreenConstraints>,
 ) {
     let Ok(window) = window.single() else { return };
-    let mut bg = Sprite::from_image(asset_server.load("background.png"));
+    let Some(level) = level.get() else {
+        warn!("Started a game before its level loaded");
+        return;
+    };
+
+    info!("Starting {}", level.name);
+    let mut bg = Sprite::from_image(level.background.clone());
     bg.custom_size = Some(Vec2::new(window.width(), window.height()));
 
     commands.spawn((bg, LevelEntity, Background));
 
+    let player_x = constraints.min_x + level.player_x * (constraints.max_x - constraints.min_x);
     commands.spawn((
-        Player::new(
-            &asset_server,
-            &mut layouts,
-            &mut animation_layouts,
-            *constraints,
-        ),
+        Player::new(&player_spritesheet, *constraints, player_x),
         LevelEntity,
     ));
 
+    if let Some(music) = &level.music {
+        commands.spawn((
+            AudioPlayer::new(music.clone()),
+            PlaybackSettings::LOOP,
+            LevelEntity,
+        ));
+    }
+
     commands.spawn((
         Node {
             margin: UiRect::axes(px(110), px(10)),
             ..default()
         },
         Text::new("Score: 0"),
-        TextColor(Color::BLACK),
+        TextColor(level.hud_color),
         ScoreText,
         LevelEntity,
     ));
     score_stopwatch.reset();
     score_stopwatch.unpause();
+    difficulty.reset(level.difficulty.clone());
 }
 
-fn handle_escape(keys: Res<ButtonInput<KeyCode>>, mut next_state: ResMut<NextState<GameState>>) {
+/// Escape pauses a run and leaves a finished one, the pause menu handles it while paused.
+fn handle_escape(
+    keys: Res<ButtonInput<KeyCode>>,
+    state: Res<State<InGameState>>,
+    mut next_in_game_state: ResMut<NextState<InGameState>>,
+    mut next_state: ResMut<NextState<GameState>>,
+) {
     if !keys.just_pressed(KeyCode::Escape) {
         return;
     };
 
-    next_state.set(GameState::Menu);
+    match state.get() {
+        InGameState::Running => next_in_game_state.set(InGameState::Paused),
+        // The pause menu goes back through its own screens
+        InGameState::Paused => {}
+        InGameState::GameOver | InGameState::LevelComplete => next_state.set(GameState::Menu),
+    }
 }
 
 fn teardown_level(mut commands: Commands, entities: Query<Entity, With<LevelEntity>>) {
@@ -108,55 +143,40 @@ fn teardown_level(mut commands: Commands, entities: Query<Entity, With<LevelEnti
     }
 }
 
-fn spawn_debris(
-    mut commands: Commands,
-    data: Res<DebrisData>,
-    asset_server: Res<AssetServer>,
-    mut debris_timer: ResMut<DebrisTimer>,
-    time: Res<Time>,
-    constraints: Res<ScreenConstraints>,
-) {
-    if !debris_timer.tick(time.delta()).just_finished() {
-        return;
-    }
-
-    commands.spawn((
-        LevelEntity,
-        Debris::new_random(&data, *constraints, &asset_server),
-    ));
-}
-
 fn check_collision(
-    debris_data: Res<DebrisData>,
-    player: Query<&Transform, With<Player>>,
-    debris: Query<(&Transform, &Debris)>,
+    debris_data: Option<Res<DebrisData>>,
+    player: Query<(&PhysicalTransform, &PreviousPhysicalTransform), With<Player>>,
+    debris: Query<(&PhysicalTransform, &PreviousPhysicalTransform, &Debris)>,
     mut next_state: ResMut<NextState<InGameState>>,
     mut score_stopwatch: ResMut<ScoreStopwatch>,
     constraints: Res<ScreenConstraints>,
+    preset: Res<Preset>,
 ) {
-    let Ok(transform) = player.single() else {
+    let (Ok((player_transform, player_previous)), Some(debris_data)) =
+        (player.single(), debris_data)
+    else {
         return;
     };
 
-    let player_rect = Rect::from_center_size(
-        transform.translation.truncate(),
-        Vec2::new(
-            COLL_WIDTH * constraints.scale,
-            COLL_HEIGHT * constraints.scale,
-        ),
-    );
-
-    for (transform, debris) in debris.iter() {
-        let definition = &debris_data.definitions[debris.definition_idx];
-        let debris_rect = Rect::from_center_size(
-            transform.translation.truncate(),
-            Vec2::new(
-                definition.coll_width * constraints.scale,
-                definition.coll_height * constraints.scale,
-            ),
-        );
+    for (transform, previous, debris) in debris.iter() {
+        let Some(definition) = debris_data.definitions.get(debris.definition_idx) else {
+            continue;
+        };
 
-        if !player_rect.intersect(debris_rect).is_empty() {
+        if collision::sweep(
+            (
+                &player::COLLIDER,
+                constraints.scale * preset.tuning().hitbox,
+                player_transform,
+                player_previous,
+            ),
+            (
+                &definition.collider,
+                constraints.scale * debris.size(),
+                transform,
+                previous,
+            ),
+        ) {
             next_state.set(InGameState::GameOver);
             score_stopwatch.pause();
             return;
@@ -164,24 +184,88 @@ fn check_collision(
     }
 }
 
+/// Ends the run once the level's goal is reached.
+fn check_goal(
+    level: LoadedLevel,
+    mut next_state: ResMut<NextState<InGameState>>,
+    mut score_stopwatch: ResMut<ScoreStopwatch>,
+) {
+    // Getting hit on the last step still ends the run
+    if matches!(*next_state, NextState::Pending(InGameState::GameOver)) {
+        return;
+    }
+
+    if level
+        .get()
+        .is_some_and(|level| level.win.is_met(score_stopwatch.elapsed_secs()))
+    {
+        next_state.set(InGameState::LevelComplete);
+        score_stopwatch.pause();
+    }
+}
+
+/// Counts simulated time, so the score doesn't depend on the frame rate.
+fn tick_score(mut score_stopwatch: ResMut<ScoreStopwatch>, time: Res<Time>) {
+    score_stopwatch.tick(time.delta());
+}
+
 fn update_score(
     mut score: Query<&mut Text, With<ScoreText>>,
-    mut score_stopwatch: ResMut<ScoreStopwatch>,
-    time: Res<Time>,
+    score_stopwatch: Res<ScoreStopwatch>,
 ) {
     let Ok(mut text) = score.single_mut() else {
         return;
     };
 
-    text.0 = format!(
-        "SCORE: {}",
-        score_stopwatch.tick(time.delta()).elapsed_secs().floor()
-    );
+    text.0 = format!("SCORE: {}", score_stopwatch.elapsed_secs().floor());
 }
 
-fn show_gameover_screen(mut commands: Commands, score_stopwatch: Res<ScoreStopwatch>) {
+fn show_gameover_screen(
+    mut commands: Commands,
+    score_stopwatch: Res<ScoreStopwatch>,
+    mut highscores: ResMut<Highscores>,
+    preset: Res<Preset>,
+    current_level: Res<CurrentLevel>,
+) {
+    let score = score_stopwatch.elapsed_secs().floor() as u32;
+    let rank =
+        level_id(&current_level.0).and_then(|id| highscores.record(id, preset.selected, score));
+
     commands.spawn((
         LevelEntity,
+        result_screen("GAMEOVER", score, rank, preset.selected),
+    ));
+}
+
+fn show_level_complete_screen(
+    mut commands: Commands,
+    score_stopwatch: Res<ScoreStopwatch>,
+    mut highscores: ResMut<Highscores>,
+    preset: Res<Preset>,
+    current_level: Res<CurrentLevel>,
+    mut progress: ResMut<Progress>,
+) {
+    let score = score_stopwatch.elapsed_secs().floor() as u32;
+    let rank = level_id(&current_level.0).and_then(|id| {
+        progress.mark_cleared(id.clone());
+        highscores.record(id, preset.selected, score)
+    });
+
+    commands.spawn((
+        LevelEntity,
+        result_screen("STAGE CLEAR", score, rank, preset.selected),
+    ));
+}
+
+/// Screen ending a run with `score`, at `rank` in the level's high scores for the preset if it
+/// made it in.
+fn result_screen(
+    title: &str,
+    score: u32,
+    rank: Option<usize>,
+    preset: DifficultyPreset,
+) -> impl Bundle + use<> {
+    (
         Node {
             width: percent(100.),
             height: percent(100.),
@@ -193,7 +277,7 @@ fn show_gameover_screen(mut commands: Commands, score_stopwatch: Res<ScoreStopwa
         BackgroundColor(MENU_BG_COLOR),
         children![
             (
-                Text::new("GAMEOVER"),
+                Text::new(title),
                 TextFont {
                     font_size: 64.,
                     ..default()
@@ -204,14 +288,29 @@ fn show_gameover_screen(mut commands: Commands, score_stopwatch: Res<ScoreStopwa
                     margin: UiRect::top(px(30)),
                     ..default()
                 },
-                Text::new(format!("SCORE: {}", score_stopwatch.elapsed_secs().floor())),
+                Text::new(format!("SCORE: {score}")),
                 TextFont {
                     font_size: 32.,
                     ..default()
                 }
             ),),
+            (
+                Node {
+                    margin: UiRect::top(px(16)),
+                    ..default()
+                },
+                Text::new(match rank {
+                    Some(0) => format!("NEW {} BEST!", preset.name().to_uppercase()),
+                    Some(rank) => format!("#{} ON {}", rank + 1, preset.name().to_uppercase()),
+                    None => preset.name().to_uppercase(),
+                }),
+                TextFont {
+                    font_size: 24.,
+                    ..default()
+                }
+            ),
         ],
-    ));
+    )
 }
 
 fn handle_resize(
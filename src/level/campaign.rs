@@ -0,0 +1,99 @@
+use bevy::{
+    asset::{AssetLoader, LoadContext, ParseAssetPathError, io::Reader},
+    prelude::*,
+};
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+use super::LevelDefinition;
+use crate::save::Progress;
+
+const CAMPAIGN_PATH: &str = "levels/campaign.json";
+
+#[derive(Error, Debug)]
+pub(crate) enum CampaignLoaderError {
+    #[error("could not read campaign: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("could not parse campaign: {0}")]
+    Json(#[from] serde_json::Error),
+    #[error("invalid level path in campaign: {0}")]
+    Path(#[from] ParseAssetPathError),
+    #[error("campaign has no levels")]
+    Empty,
+}
+
+/// The campaign as written in its file, level paths are relative to it.
+#[derive(Serialize, Deserialize)]
+struct CampaignFile {
+    levels: Vec<String>,
+}
+
+/// The levels in the order they are unlocked, each once the previous one is cleared.
+#[derive(Asset, TypePath)]
+pub(crate) struct Campaign {
+    #[dependency]
+    pub(crate) levels: Vec<Handle<LevelDefinition>>,
+}
+
+impl Campaign {
+    pub(crate) fn is_unlocked(&self, idx: usize, progress: &Progress) -> bool {
+        idx == 0
+            || self
+                .levels
+                .get(idx - 1)
+                .and_then(level_id)
+                .is_some_and(|id| progress.is_cleared(&id))
+    }
+}
+
+/// What a level's progress is saved under.
+pub(crate) fn level_id(level: &Handle<LevelDefinition>) -> Option<String> {
+    level.path().map(ToString::to_string)
+}
+
+#[derive(Default)]
+pub(crate) struct CampaignLoader;
+
+impl AssetLoader for CampaignLoader {
+    type Asset = Campaign;
+    type Settings = ();
+    type Error = CampaignLoaderError;
+
+    async fn load(
+        &self,
+        reader: &mut dyn Reader,
+        _settings: &(),
+        load_context: &mut LoadContext<'_>,
+    ) -> Result<Self::Asset, Self::Error> {
+        let mut bytes = Vec::new();
+        reader.read_to_end(&mut bytes).await?;
+        let file: CampaignFile = serde_json::from_slice(&bytes)?;
+
+        if file.levels.is_empty() {
+            return Err(CampaignLoaderError::Empty);
+        }
+
+        let base = load_context.asset_path().clone();
+        let levels = file
+            .levels
+            .iter()
+            .map(|path| Ok(load_context.load(base.resolve_embed(path)?)))
+            .collect::<Result<_, CampaignLoaderError>>()?;
+
+        Ok(Campaign { levels })
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["json"]
+    }
+}
+
+/// Keeps the campaign and its levels loaded.
+#[derive(Resource)]
+pub(crate) struct CampaignHandle(pub(crate) Handle<Campaign>);
+
+impl FromWorld for CampaignHandle {
+    fn from_world(world: &mut World) -> Self {
+        CampaignHandle(world.resource::<AssetServer>().load(CAMPAIGN_PATH))
+    }
+}
@@ -0,0 +1,168 @@
+use bevy::{
+    asset::{AssetLoader, LoadContext, ParseAssetPathError, io::Reader},
+    ecs::system::SystemParam,
+    prelude::*,
+};
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+use crate::{difficulty::DifficultyCurve, waves::WaveData};
+
+#[derive(Error, Debug)]
+pub(crate) enum LevelLoaderError {
+    #[error("could not read level: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("could not parse level: {0}")]
+    Json(#[from] serde_json::Error),
+    #[error("invalid path in level: {0}")]
+    Path(#[from] ParseAssetPathError),
+    #[error("the player must start between 0 and 1 across the screen, not {0}")]
+    InvalidPlayerX(f32),
+    #[error("the level must be survived for a positive time")]
+    InvalidGoal,
+}
+
+/// A stage as written in its file. Paths are relative to the file, or to the assets folder
+/// when they start with `/`.
+#[derive(Serialize, Deserialize)]
+struct LevelFile {
+    name: String,
+    background: String,
+    #[serde(default)]
+    music: Option<String>,
+    /// Names of the debris picked at random, all of them if empty
+    #[serde(default)]
+    debris: Vec<String>,
+    waves: String,
+    difficulty: String,
+    #[serde(default)]
+    win: WinCondition,
+    /// Where the player starts, as a fraction of the screen width
+    #[serde(default = "default_player_x")]
+    player_x: f32,
+    /// Color of the score, to stand out from the background
+    #[serde(default)]
+    hud_color: [u8; 3],
+}
+
+fn default_player_x() -> f32 {
+    0.5
+}
+
+#[derive(Asset, TypePath)]
+pub(crate) struct LevelDefinition {
+    pub(crate) name: String,
+    #[dependency]
+    pub(crate) background: Handle<Image>,
+    #[dependency]
+    pub(crate) music: Option<Handle<AudioSource>>,
+    pub(crate) debris_pool: Vec<String>,
+    #[dependency]
+    pub(crate) waves: Handle<WaveData>,
+    #[dependency]
+    pub(crate) difficulty: Handle<DifficultyCurve>,
+    pub(crate) win: WinCondition,
+    pub(crate) player_x: f32,
+    pub(crate) hud_color: Color,
+}
+
+#[derive(Serialize, Deserialize, Default, Debug, Clone, Copy)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub(crate) enum WinCondition {
+    /// The run lasts until the player is hit
+    #[default]
+    Endless,
+    /// The level is cleared after surviving `secs`
+    Survive { secs: f32 },
+}
+
+impl WinCondition {
+    pub(crate) fn is_met(&self, elapsed_secs: f32) -> bool {
+        match *self {
+            WinCondition::Endless => false,
+            WinCondition::Survive { secs } => elapsed_secs >= secs,
+        }
+    }
+
+    pub(crate) fn description(&self) -> String {
+        match *self {
+            WinCondition::Endless => "Endless".to_string(),
+            WinCondition::Survive { secs } => format!("Survive {secs}s"),
+        }
+    }
+}
+
+#[derive(Default)]
+pub(crate) struct LevelLoader;
+
+impl AssetLoader for LevelLoader {
+    type Asset = LevelDefinition;
+    type Settings = ();
+    type Error = LevelLoaderError;
+
+    async fn load(
+        &self,
+        reader: &mut dyn Reader,
+        _settings: &(),
+        load_context: &mut LoadContext<'_>,
+    ) -> Result<Self::Asset, Self::Error> {
+        let mut bytes = Vec::new();
+        reader.read_to_end(&mut bytes).await?;
+        let file: LevelFile = serde_json::from_slice(&bytes)?;
+
+        if !(0.0..=1.0).contains(&file.player_x) {
+            return Err(LevelLoaderError::InvalidPlayerX(file.player_x));
+        }
+
+        if matches!(file.win, WinCondition::Survive { secs } if !secs.is_finite() || secs <= 0.0) {
+            return Err(LevelLoaderError::InvalidGoal);
+        }
+
+        let base = load_context.asset_path().clone();
+        let music = match &file.music {
+            Some(path) => Some(load_context.load(base.resolve_embed(path)?)),
+            None => None,
+        };
+
+        Ok(LevelDefinition {
+            name: file.name,
+            background: load_context.load(base.resolve_embed(&file.background)?),
+            music,
+            debris_pool: file.debris,
+            waves: load_context.load(base.resolve_embed(&file.waves)?),
+            difficulty: load_context.load(base.resolve_embed(&file.difficulty)?),
+            win: file.win,
+            player_x: file.player_x,
+            hud_color: Color::srgb_u8(file.hud_color[0], file.hud_color[1], file.hud_color[2]),
+        })
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["json"]
+    }
+}
+
+/// The level the next run is played on, picked in the level select.
+#[derive(Resource, Default)]
+pub(crate) struct CurrentLevel(pub(crate) Handle<LevelDefinition>);
+
+/// The [`CurrentLevel`]'s definition, once it and everything it refers to has loaded.
+#[derive(SystemParam)]
+pub(crate) struct LoadedLevel<'w> {
+    current: Res<'w, CurrentLevel>,
+    levels: Res<'w, Assets<LevelDefinition>>,
+    asset_server: Res<'w, AssetServer>,
+}
+
+impl LoadedLevel<'_> {
+    pub(crate) fn get(&self) -> Option<&LevelDefinition> {
+        if !self
+            .asset_server
+            .is_loaded_with_dependencies(&self.current.0)
+        {
+            return None;
+        }
+
+        self.levels.get(&self.current.0)
+    }
+}
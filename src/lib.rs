@@ -2,10 +2,19 @@
 #![allow(clippy::new_ret_no_self)]
 
 mod animation;
+mod collision;
+mod coworker;
 mod debris;
+mod difficulty;
 mod game;
 mod level;
 mod menu;
+mod pause;
+mod physics;
 mod player;
+mod preset;
+mod save;
+mod telegraph;
+mod waves;
 
 pub use game::GamePlugin;
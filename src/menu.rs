@@ -1,4 +1,7 @@
-use crate::game::GameState;
+use crate::{
+    game::GameState,
+    preset::{DifficultyPreset, Preset, TUNING_STEP, TuningField},
+};
 use bevy::{
     input_focus::InputDispatchPlugin,
     picking::hover::Hovered,
@@ -6,26 +9,57 @@ use bevy::{
     ui_widgets::{Activate, Button, UiWidgetsPlugins, observe},
 };
 
+mod level_select;
+
 pub(crate) const MENU_BG_COLOR: Color = Color::srgb_u8(43, 44, 47);
+const SELECTED_COLOR: Color = Color::srgb_u8(255, 196, 0);
 
 pub(crate) struct MenuPlugin;
 
 impl Plugin for MenuPlugin {
     fn build(&self, app: &mut App) {
         app.add_plugins((UiWidgetsPlugins, InputDispatchPlugin))
-            .add_systems(OnEnter(GameState::Menu), setup_menu)
+            .add_sub_state::<MenuScreen>()
+            .add_systems(OnEnter(MenuScreen::Main), setup_menu)
+            .add_systems(
+                OnEnter(MenuScreen::LevelSelect),
+                level_select::setup_level_select,
+            )
+            .add_systems(Update, button_hovered)
+            .add_systems(
+                Update,
+                (
+                    handle_enter,
+                    refresh_menu.run_if(resource_changed::<Preset>),
+                )
+                    .run_if(in_state(MenuScreen::Main)),
+            )
             .add_systems(
                 Update,
-                (button_hovered, handle_enter).run_if(in_state(GameState::Menu)),
+                (
+                    level_select::handle_level_select_keys,
+                    level_select::refresh_level_select,
+                )
+                    .run_if(in_state(MenuScreen::LevelSelect)),
             )
-            .add_systems(OnExit(GameState::Menu), teardown_menu);
+            .add_systems(OnExit(MenuScreen::Main), teardown_menu)
+            .add_systems(OnExit(MenuScreen::LevelSelect), teardown_menu);
     }
 }
 
+/// Which screen of the menu is showing.
+#[derive(Default, SubStates, Debug, Clone, PartialEq, Eq, Hash)]
+#[source(GameState = GameState::Menu)]
+enum MenuScreen {
+    #[default]
+    Main,
+    LevelSelect,
+}
+
 #[derive(Component)]
 pub struct MenuRoot;
 
-fn setup_menu(mut commands: Commands) {
+fn setup_menu(mut commands: Commands, preset: Res<Preset>) {
     commands
         .spawn((
             GlobalTransform::default(),
@@ -50,50 +84,111 @@ fn setup_menu(mut commands: Commands) {
             ));
 
             commands.spawn((
-                GlobalTransform::default(),
-                Node {
-                    width: px(200),
-                    padding: UiRect::axes(px(16), px(8)),
-                    margin: UiRect::top(px(180)),
-                    border: UiRect::all(px(1)),
-                    justify_content: JustifyContent::Center,
-                    align_items: AlignItems::Center,
-                    ..default()
-                },
-                Hovered::default(),
-                BackgroundColor(Color::srgba(0., 0., 0., 0.)),
-                BorderColor::all(Color::WHITE),
-                BorderRadius::all(px(12)),
-                Button,
-                children![(Text::new("New Game"),)],
+                button("New Game", px(200), UiRect::top(px(64))),
                 observe(new_game),
             ));
 
-            #[cfg(not(target_arch = "wasm32"))]
-            commands.spawn((
-                GlobalTransform::default(),
-                Node {
-                    width: px(200),
-                    padding: UiRect::axes(px(16), px(8)),
-                    margin: UiRect::top(px(16)),
-                    border: UiRect::all(px(1)),
-                    justify_content: JustifyContent::Center,
-                    align_items: AlignItems::Center,
+            commands
+                .spawn(Node {
+                    margin: UiRect::top(px(32)),
+                    column_gap: px(8),
                     ..default()
-                },
-                Hovered::default(),
-                BackgroundColor(Color::srgba(0., 0., 0., 0.)),
-                BorderColor::all(Color::WHITE),
-                BorderRadius::all(px(12)),
-                Button,
-                children![(Text::new("Exit"),)],
-                observe(exit),
-            ));
+                })
+                .with_children(|commands| {
+                    for option in DifficultyPreset::ALL {
+                        let mut button = commands.spawn((
+                            button(option.name(), px(100), UiRect::ZERO),
+                            observe(move |_: On<Activate>, mut preset: ResMut<Preset>| {
+                                preset.selected = option;
+                            }),
+                        ));
+
+                        if option == preset.selected {
+                            button.insert(BorderColor::all(SELECTED_COLOR));
+                        }
+                    }
+                });
+
+            if preset.selected == DifficultyPreset::Custom {
+                for field in TuningField::ALL {
+                    commands.spawn((
+                        Node {
+                            margin: UiRect::top(px(8)),
+                            column_gap: px(8),
+                            align_items: AlignItems::Center,
+                            ..default()
+                        },
+                        children![
+                            (
+                                Node {
+                                    width: px(140),
+                                    ..default()
+                                },
+                                Text::new(field.name()),
+                            ),
+                            (
+                                button("-", px(40), UiRect::ZERO),
+                                observe(move |_: On<Activate>, mut preset: ResMut<Preset>| {
+                                    preset.custom.adjust(field, -TUNING_STEP);
+                                }),
+                            ),
+                            (
+                                Node {
+                                    width: px(60),
+                                    justify_content: JustifyContent::Center,
+                                    ..default()
+                                },
+                                Text::new(format!("{:.0}%", preset.custom.get(field) * 100.0)),
+                            ),
+                            (
+                                button("+", px(40), UiRect::ZERO),
+                                observe(move |_: On<Activate>, mut preset: ResMut<Preset>| {
+                                    preset.custom.adjust(field, TUNING_STEP);
+                                }),
+                            ),
+                        ],
+                    ));
+                }
+            }
+
+            #[cfg(not(target_arch = "wasm32"))]
+            commands.spawn((button("Exit", px(200), UiRect::top(px(32))), observe(exit)));
         });
 }
 
-fn new_game(_: On<Activate>, mut next_state: ResMut<NextState<GameState>>) {
-    next_state.set(GameState::InGame);
+pub(crate) fn button(label: &str, width: Val, margin: UiRect) -> impl Bundle + use<> {
+    (
+        GlobalTransform::default(),
+        Node {
+            width,
+            padding: UiRect::axes(px(16), px(8)),
+            margin,
+            border: UiRect::all(px(1)),
+            justify_content: JustifyContent::Center,
+            align_items: AlignItems::Center,
+            ..default()
+        },
+        Hovered::default(),
+        BackgroundColor(Color::srgba(0., 0., 0., 0.)),
+        BorderColor::all(Color::WHITE),
+        BorderRadius::all(px(12)),
+        Button,
+        children![(Text::new(label),)],
+    )
+}
+
+/// Rebuilds the menu to show a newly selected preset or custom tuning.
+fn refresh_menu(mut commands: Commands, menu: Query<Entity, With<MenuRoot>>, preset: Res<Preset>) {
+    if menu.is_empty() {
+        return;
+    }
+
+    teardown_menu(commands.reborrow(), menu);
+    setup_menu(commands, preset);
+}
+
+fn new_game(_: On<Activate>, mut next_screen: ResMut<NextState<MenuScreen>>) {
+    next_screen.set(MenuScreen::LevelSelect);
 }
 
 #[allow(unused)]
@@ -111,12 +206,12 @@ fn button_hovered(mut buttons: Query<(&mut BackgroundColor, &Hovered), With<Butt
     }
 }
 
-fn handle_enter(input: Res<ButtonInput<KeyCode>>, mut next_state: ResMut<NextState<GameState>>) {
+fn handle_enter(input: Res<ButtonInput<KeyCode>>, mut next_screen: ResMut<NextState<MenuScreen>>) {
     if !input.just_pressed(KeyCode::Enter) {
         return;
     }
 
-    next_state.set(GameState::InGame);
+    next_screen.set(MenuScreen::LevelSelect);
 }
 
 fn teardown_menu(mut commands: Commands, menu: Query<Entity, With<MenuRoot>>) {
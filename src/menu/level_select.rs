@@ -0,0 +1,239 @@
+use bevy::{
+    ecs::system::SystemParam,
+    prelude::*,
+    ui_widgets::{Activate, observe},
+};
+
+use super::{MENU_BG_COLOR, MenuRoot, MenuScreen, button, teardown_menu};
+use crate::{
+    game::GameState,
+    level::{Campaign, CampaignHandle, CurrentLevel, LevelDefinition, level_id},
+    preset::Preset,
+    save::{Highscores, Progress},
+};
+
+const LOCKED_COLOR: Color = Color::srgb_u8(120, 120, 120);
+const FAILED_COLOR: Color = Color::srgb_u8(220, 90, 80);
+
+/// The campaign's levels, how far the player got through them and their best scores.
+#[derive(SystemParam)]
+pub(super) struct CampaignProgress<'w> {
+    handle: Res<'w, CampaignHandle>,
+    campaigns: Res<'w, Assets<Campaign>>,
+    levels: Res<'w, Assets<LevelDefinition>>,
+    asset_server: Res<'w, AssetServer>,
+    progress: Res<'w, Progress>,
+    highscores: Res<'w, Highscores>,
+    preset: Res<'w, Preset>,
+}
+
+impl CampaignProgress<'_> {
+    /// The campaign, once every level in it has either loaded or failed to.
+    fn campaign(&self) -> Option<&Campaign> {
+        let campaign = self.campaigns.get(&self.handle.0)?;
+        let settled = campaign.levels.iter().all(|level| {
+            let state = self.asset_server.recursive_dependency_load_state(level);
+            state.is_loaded() || state.is_failed()
+        });
+
+        settled.then_some(campaign)
+    }
+
+    fn failed(&self) -> bool {
+        self.asset_server.load_state(&self.handle.0).is_failed()
+    }
+
+    fn is_playable(&self, level: &Handle<LevelDefinition>) -> bool {
+        self.asset_server.is_loaded_with_dependencies(level)
+    }
+
+    fn last_unlocked(&self) -> Option<Handle<LevelDefinition>> {
+        let campaign = self.campaign()?;
+        (0..campaign.levels.len())
+            .rev()
+            .find(|&idx| campaign.is_unlocked(idx, &self.progress))
+            .map(|idx| campaign.levels[idx].clone())
+            .filter(|level| self.is_playable(level))
+    }
+}
+
+/// Replaced by the levels once they finish loading.
+#[derive(Component)]
+pub(super) struct LoadingLevels;
+
+pub(super) fn setup_level_select(mut commands: Commands, campaign: CampaignProgress) {
+    commands
+        .spawn((
+            GlobalTransform::default(),
+            MenuRoot,
+            Node {
+                width: percent(100),
+                height: percent(100),
+                flex_direction: FlexDirection::Column,
+                align_items: AlignItems::Center,
+                margin: UiRect::top(px(64)),
+                ..default()
+            },
+            BackgroundColor(MENU_BG_COLOR),
+        ))
+        .with_children(|commands| {
+            commands.spawn((
+                Text::new("SELECT LEVEL"),
+                TextFont {
+                    font_size: 48.,
+                    ..default()
+                },
+            ));
+
+            if campaign.failed() {
+                commands.spawn((
+                    Node {
+                        margin: UiRect::top(px(32)),
+                        ..default()
+                    },
+                    Text::new("Could not load the campaign"),
+                    TextColor(FAILED_COLOR),
+                ));
+            }
+
+            let Some(levels) = campaign.campaign() else {
+                if !campaign.failed() {
+                    commands.spawn((
+                        Node {
+                            margin: UiRect::top(px(32)),
+                            ..default()
+                        },
+                        Text::new("Loading..."),
+                        LoadingLevels,
+                    ));
+                }
+                commands.spawn((button("Back", px(200), UiRect::top(px(32))), observe(back)));
+                return;
+            };
+
+            for (idx, handle) in levels.levels.iter().enumerate() {
+                // The level or something it uses failed to load, the log says what
+                if !campaign.is_playable(handle) {
+                    commands.spawn((
+                        Node {
+                            width: px(560),
+                            padding: UiRect::axes(px(16), px(8)),
+                            margin: UiRect::top(px(12)),
+                            justify_content: JustifyContent::Center,
+                            ..default()
+                        },
+                        Text::new(format!(
+                            "{}. Could not load {}",
+                            idx + 1,
+                            level_id(handle).unwrap_or_default()
+                        )),
+                        TextColor(FAILED_COLOR),
+                    ));
+                    continue;
+                }
+
+                let Some(level) = campaign.levels.get(handle) else {
+                    continue;
+                };
+
+                if !levels.is_unlocked(idx, &campaign.progress) {
+                    commands.spawn((
+                        Node {
+                            width: px(560),
+                            padding: UiRect::axes(px(16), px(8)),
+                            margin: UiRect::top(px(12)),
+                            justify_content: JustifyContent::Center,
+                            ..default()
+                        },
+                        Text::new(format!("{}. Locked", idx + 1)),
+                        TextColor(LOCKED_COLOR),
+                    ));
+                    continue;
+                }
+
+                let id = level_id(handle).unwrap_or_default();
+                let cleared = campaign.progress.is_cleared(&id);
+                let best = campaign
+                    .highscores
+                    .table(&id, campaign.preset.selected)
+                    .first()
+                    .map(|score| format!(" - best {score}"));
+                let label = format!(
+                    "{}. {} - {}{}{}",
+                    idx + 1,
+                    level.name,
+                    level.win.description(),
+                    best.unwrap_or_default(),
+                    if cleared { " (cleared)" } else { "" }
+                );
+
+                let handle = handle.clone();
+                commands.spawn((
+                    button(&label, px(560), UiRect::top(px(12))),
+                    observe(
+                        move |_: On<Activate>,
+                              mut commands: Commands,
+                              mut next_state: ResMut<NextState<GameState>>| {
+                            commands.insert_resource(CurrentLevel(handle.clone()));
+                            next_state.set(GameState::InGame);
+                        },
+                    ),
+                ));
+            }
+
+            commands.spawn((button("Back", px(200), UiRect::top(px(32))), observe(back)));
+        });
+}
+
+/// Shows the levels once the campaign finishes loading, or again when it's hot reloaded.
+pub(super) fn refresh_level_select(
+    mut commands: Commands,
+    mut events: MessageReader<AssetEvent<Campaign>>,
+    menu: Query<Entity, With<MenuRoot>>,
+    loading: Query<(), With<LoadingLevels>>,
+    campaign: CampaignProgress,
+) {
+    let modified = events.read().any(|event| {
+        matches!(
+            *event,
+            AssetEvent::LoadedWithDependencies { id } | AssetEvent::Modified { id }
+                if id == campaign.handle.0.id()
+        )
+    });
+    // Levels that fail to load never send an event, so the loading screen keeps checking
+    let settled = !loading.is_empty() && (campaign.campaign().is_some() || campaign.failed());
+
+    if (!modified && !settled) || menu.is_empty() {
+        return;
+    }
+
+    teardown_menu(commands.reborrow(), menu);
+    setup_level_select(commands, campaign);
+}
+
+fn back(_: On<Activate>, mut next_screen: ResMut<NextState<MenuScreen>>) {
+    next_screen.set(MenuScreen::Main);
+}
+
+/// Enter plays the furthest unlocked level, Escape goes back.
+pub(super) fn handle_level_select_keys(
+    mut commands: Commands,
+    input: Res<ButtonInput<KeyCode>>,
+    campaign: CampaignProgress,
+    mut next_screen: ResMut<NextState<MenuScreen>>,
+    mut next_state: ResMut<NextState<GameState>>,
+) {
+    if input.just_pressed(KeyCode::Escape) {
+        next_screen.set(MenuScreen::Main);
+        return;
+    }
+
+    if !input.just_pressed(KeyCode::Enter) {
+        return;
+    }
+
+    if let Some(level) = campaign.last_unlocked() {
+        commands.insert_resource(CurrentLevel(level));
+        next_state.set(GameState::InGame);
+    }
+}
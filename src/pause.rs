@@ -0,0 +1,232 @@
+use bevy::{
+    prelude::*,
+    ui_widgets::{Activate, observe},
+    window::WindowFocused,
+};
+
+use crate::{
+    game::{GameState, InGameState},
+    level::ScoreStopwatch,
+    menu::{MENU_BG_COLOR, button},
+    telegraph::TelegraphSettings,
+};
+
+pub(crate) struct PausePlugin;
+
+impl Plugin for PausePlugin {
+    fn build(&self, app: &mut App) {
+        app.add_sub_state::<PauseScreen>()
+            .add_systems(OnEnter(InGameState::Paused), pause)
+            .add_systems(OnExit(InGameState::Paused), resume)
+            .add_systems(OnEnter(PauseScreen::Main), setup_pause_menu)
+            .add_systems(OnEnter(PauseScreen::Settings), setup_settings)
+            .add_systems(OnExit(PauseScreen::Main), teardown_pause_menu)
+            .add_systems(OnExit(PauseScreen::Settings), teardown_pause_menu)
+            .add_systems(OnEnter(GameState::Restarting), finish_restart)
+            .add_systems(
+                Update,
+                refresh_settings.run_if(
+                    in_state(PauseScreen::Settings).and(resource_changed::<TelegraphSettings>),
+                ),
+            )
+            .add_systems(
+                Update,
+                pause_on_focus_loss.run_if(in_state(InGameState::Running)),
+            )
+            .add_systems(Update, handle_escape.run_if(in_state(InGameState::Paused)));
+    }
+}
+
+/// Which screen of the pause menu is showing.
+#[derive(Default, SubStates, Debug, Clone, PartialEq, Eq, Hash)]
+#[source(InGameState = InGameState::Paused)]
+enum PauseScreen {
+    #[default]
+    Main,
+    Settings,
+}
+
+#[derive(Component)]
+struct PauseMenuRoot;
+
+/// Freezes the simulation along with everything else following virtual time, and the music.
+fn pause(
+    mut time: ResMut<Time<Virtual>>,
+    mut score_stopwatch: ResMut<ScoreStopwatch>,
+    music: Query<&AudioSink>,
+) {
+    time.pause();
+    score_stopwatch.pause();
+    for sink in music.iter() {
+        sink.pause();
+    }
+}
+
+fn resume(
+    mut time: ResMut<Time<Virtual>>,
+    mut score_stopwatch: ResMut<ScoreStopwatch>,
+    music: Query<&AudioSink>,
+) {
+    time.unpause();
+    score_stopwatch.unpause();
+    for sink in music.iter() {
+        sink.play();
+    }
+}
+
+fn pause_on_focus_loss(
+    mut events: MessageReader<WindowFocused>,
+    mut next_state: ResMut<NextState<InGameState>>,
+) {
+    if events.read().any(|event| !event.focused) {
+        next_state.set(InGameState::Paused);
+    }
+}
+
+/// Escape leaves the settings for the main pause screen, and resumes from there.
+fn handle_escape(
+    keys: Res<ButtonInput<KeyCode>>,
+    screen: Res<State<PauseScreen>>,
+    mut next_screen: ResMut<NextState<PauseScreen>>,
+    mut next_state: ResMut<NextState<InGameState>>,
+) {
+    if !keys.just_pressed(KeyCode::Escape) {
+        return;
+    }
+
+    match screen.get() {
+        PauseScreen::Main => next_state.set(InGameState::Running),
+        PauseScreen::Settings => next_screen.set(PauseScreen::Main),
+    }
+}
+
+fn pause_menu_root() -> impl Bundle {
+    (
+        GlobalTransform::default(),
+        PauseMenuRoot,
+        Node {
+            width: percent(100),
+            height: percent(100),
+            flex_direction: FlexDirection::Column,
+            justify_content: JustifyContent::Center,
+            align_items: AlignItems::Center,
+            ..default()
+        },
+        BackgroundColor(MENU_BG_COLOR.with_alpha(0.85)),
+        // Above the HUD
+        GlobalZIndex(1),
+    )
+}
+
+fn setup_pause_menu(mut commands: Commands) {
+    commands.spawn(pause_menu_root()).with_children(|commands| {
+        commands.spawn((
+            Text::new("PAUSED"),
+            TextFont {
+                font_size: 64.,
+                ..default()
+            },
+        ));
+
+        commands.spawn((
+            button("Resume", px(200), UiRect::top(px(48))),
+            observe(resume_game),
+        ));
+        commands.spawn((
+            button("Restart", px(200), UiRect::top(px(16))),
+            observe(
+                |_: On<Activate>, mut next_state: ResMut<NextState<GameState>>| {
+                    next_state.set(GameState::Restarting);
+                },
+            ),
+        ));
+        commands.spawn((
+            button("Settings", px(200), UiRect::top(px(16))),
+            observe(
+                |_: On<Activate>, mut next_screen: ResMut<NextState<PauseScreen>>| {
+                    next_screen.set(PauseScreen::Settings);
+                },
+            ),
+        ));
+        commands.spawn((
+            button("Quit to Menu", px(200), UiRect::top(px(16))),
+            observe(
+                |_: On<Activate>, mut next_state: ResMut<NextState<GameState>>| {
+                    next_state.set(GameState::Menu);
+                },
+            ),
+        ));
+    });
+}
+
+fn setup_settings(mut commands: Commands, settings: Res<TelegraphSettings>) {
+    let on_off = |enabled: bool| if enabled { "On" } else { "Off" };
+
+    commands.spawn(pause_menu_root()).with_children(|commands| {
+        commands.spawn((
+            Text::new("SETTINGS"),
+            TextFont {
+                font_size: 48.,
+                ..default()
+            },
+        ));
+
+        commands.spawn((
+            button(
+                &format!("Landing shadows: {}", on_off(settings.shadow)),
+                px(300),
+                UiRect::top(px(48)),
+            ),
+            observe(|_: On<Activate>, mut settings: ResMut<TelegraphSettings>| {
+                settings.shadow = !settings.shadow;
+            }),
+        ));
+        commands.spawn((
+            button(
+                &format!("Edge markers: {}", on_off(settings.marker)),
+                px(300),
+                UiRect::top(px(16)),
+            ),
+            observe(|_: On<Activate>, mut settings: ResMut<TelegraphSettings>| {
+                settings.marker = !settings.marker;
+            }),
+        ));
+        commands.spawn((
+            button("Back", px(200), UiRect::top(px(32))),
+            observe(
+                |_: On<Activate>, mut next_screen: ResMut<NextState<PauseScreen>>| {
+                    next_screen.set(PauseScreen::Main);
+                },
+            ),
+        ));
+    });
+}
+
+/// Rebuilds the settings to show a toggled option.
+fn refresh_settings(
+    mut commands: Commands,
+    menu: Query<Entity, With<PauseMenuRoot>>,
+    settings: Res<TelegraphSettings>,
+) {
+    if menu.is_empty() {
+        return;
+    }
+
+    teardown_pause_menu(commands.reborrow(), menu);
+    setup_settings(commands, settings);
+}
+
+fn resume_game(_: On<Activate>, mut next_state: ResMut<NextState<InGameState>>) {
+    next_state.set(InGameState::Running);
+}
+
+/// Goes back into the level it left, which sets it up from scratch.
+fn finish_restart(mut next_state: ResMut<NextState<GameState>>) {
+    next_state.set(GameState::InGame);
+}
+
+fn teardown_pause_menu(mut commands: Commands, menu: Query<Entity, With<PauseMenuRoot>>) {
+    for entity in menu.iter() {
+        commands.entity(entity).despawn();
+    }
+}
@@ -0,0 +1,82 @@
+use bevy::prelude::*;
+
+pub(crate) struct PhysicsPlugin;
+
+impl Plugin for PhysicsPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(FixedFirst, record_previous_physical_transforms)
+            .add_systems(
+                RunFixedMainLoop,
+                interpolate_rendered_transforms.in_set(RunFixedMainLoopSystems::AfterFixedMainLoop),
+            );
+    }
+}
+
+/// Systems that move entities in `FixedUpdate`, collision checks run after them so the whole
+/// step's motion is swept.
+#[derive(SystemSet, Debug, Clone, PartialEq, Eq, Hash)]
+pub(crate) struct MovementSystems;
+
+/// Where the simulation has an entity, advanced in `FixedUpdate`. The rendered [`Transform`]
+/// is interpolated between this and the previous step, only its scale is left alone.
+#[derive(Component, Deref, DerefMut, Clone, Copy)]
+#[require(PreviousPhysicalTransform)]
+pub(crate) struct PhysicalTransform(pub(crate) Transform);
+
+/// The [`PhysicalTransform`] at the start of the current fixed step.
+#[derive(Component, Default)]
+pub(crate) struct PreviousPhysicalTransform(Option<Transform>);
+
+impl PreviousPhysicalTransform {
+    /// The transform a fraction `t` of the way from the start of the step to `current`.
+    pub(crate) fn lerp(&self, current: &Transform, t: f32) -> Transform {
+        let Some(previous) = self.0 else {
+            return *current;
+        };
+
+        Transform {
+            translation: previous.translation.lerp(current.translation, t),
+            rotation: previous.rotation.slerp(current.rotation, t),
+            scale: previous.scale.lerp(current.scale, t),
+        }
+    }
+
+    pub(crate) fn distance(&self, current: &Transform) -> f32 {
+        self.0.map_or(0.0, |previous| {
+            previous
+                .translation
+                .truncate()
+                .distance(current.translation.truncate())
+        })
+    }
+
+    /// Forgets the previous step, for jumps that shouldn't be interpolated or swept.
+    pub(crate) fn reset(&mut self) {
+        self.0 = None;
+    }
+}
+
+fn record_previous_physical_transforms(
+    mut query: Query<(&PhysicalTransform, &mut PreviousPhysicalTransform)>,
+) {
+    for (physical, mut previous) in query.iter_mut() {
+        previous.0 = Some(physical.0);
+    }
+}
+
+fn interpolate_rendered_transforms(
+    mut query: Query<(
+        &mut Transform,
+        &PhysicalTransform,
+        &PreviousPhysicalTransform,
+    )>,
+    time: Res<Time<Fixed>>,
+) {
+    let t = time.overstep_fraction();
+
+    for (mut transform, physical, previous) in query.iter_mut() {
+        let interpolated = previous.lerp(physical, t);
+        transform.translation = interpolated.translation;
+        transform.rotation = interpolated.rotation;
+    }
+}
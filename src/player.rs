@@ -1,29 +1,69 @@
+use std::time::Duration;
+
 use bevy::prelude::*;
 
 use crate::{
-    animation::{AnimatedSprite, AnimationTextureAtlasLayout},
+    animation::{
+        AnimatedSprite, AnimationController, AnimationSystems, AnimationTextureAtlasLayout,
+        Condition, Crossfade, PlaybackSpeedBinding, bind_playback_speed,
+    },
+    collision::Collider,
     game::{InGameState, ScreenConstraints},
+    physics::{MovementSystems, PhysicalTransform, PreviousPhysicalTransform},
+    preset::Preset,
 };
 
 const VELOCITY_X: f32 = 300.0;
 const SCALE: f32 = 0.25;
 const PLAYER_Y_DELTA: f32 = 100.0;
-pub(crate) const COLL_WIDTH: f32 = 80.0;
-pub(crate) const COLL_HEIGHT: f32 = 150.0;
+const COLL_WIDTH: f32 = 80.0;
+const COLL_HEIGHT: f32 = 150.0;
+pub(crate) const COLLIDER: Collider = Collider::Capsule {
+    radius: COLL_WIDTH / 2.0,
+    half_length: (COLL_HEIGHT - COLL_WIDTH) / 2.0,
+};
+const SPRITESHEET_PATH: &str = "character_spritesheet.json";
+const CROSSFADE_DURATION: Duration = Duration::from_millis(80);
 
 pub(crate) struct PlayerPlugin;
 
 impl Plugin for PlayerPlugin {
     fn build(&self, app: &mut App) {
-        app.add_systems(
-            Update,
-            (handle_input, movement, flip_sprite).run_if(in_state(InGameState::Running)),
-        )
-        .add_systems(
-            Update,
-            handle_resize
-                .run_if(in_state(InGameState::Running).and(resource_changed::<ScreenConstraints>)),
-        );
+        app.init_resource::<PlayerSpritesheet>()
+            .add_systems(
+                RunFixedMainLoop,
+                handle_input
+                    .in_set(RunFixedMainLoopSystems::BeforeFixedMainLoop)
+                    .run_if(in_state(InGameState::Running)),
+            )
+            .add_systems(
+                FixedUpdate,
+                movement
+                    .in_set(MovementSystems)
+                    .run_if(in_state(InGameState::Running)),
+            )
+            .add_systems(
+                Update,
+                (flip_sprite, bind_playback_speed::<Player>)
+                    .before(AnimationSystems)
+                    .run_if(in_state(InGameState::Running)),
+            )
+            .add_systems(
+                Update,
+                handle_resize.run_if(
+                    in_state(InGameState::Running).and(resource_changed::<ScreenConstraints>),
+                ),
+            );
+    }
+}
+
+/// Keeps the character spritesheet loaded between games.
+#[derive(Resource)]
+pub(crate) struct PlayerSpritesheet(Handle<AnimationTextureAtlasLayout>);
+
+impl FromWorld for PlayerSpritesheet {
+    fn from_world(world: &mut World) -> Self {
+        PlayerSpritesheet(world.resource::<AssetServer>().load(SPRITESHEET_PATH))
     }
 }
 
@@ -37,44 +77,39 @@ pub(crate) enum Direction {
 #[derive(Component)]
 #[require(Transform)]
 pub(crate) struct Player {
+    /// Horizontal velocity before screen scaling
     velocity: f32,
     direction: Direction,
 }
 
 impl Player {
     pub(crate) fn new(
-        asset_server: &AssetServer,
-        layouts: &mut Assets<TextureAtlasLayout>,
-        animation_layouts: &mut Assets<AnimationTextureAtlasLayout>,
+        spritesheet: &PlayerSpritesheet,
         constraints: ScreenConstraints,
-    ) -> impl Bundle {
-        let animation_layout = AnimationTextureAtlasLayout::from_json(
-            include_str!("../assets/character_spritesheet.json"),
-            layouts,
-        )
-        .expect("expected to load spritesheet layout");
-
-        let animation_layout_handle = animation_layouts.add(animation_layout.clone());
+        x: f32,
+    ) -> impl Bundle + use<> {
+        let transform = Transform::from_translation(Vec3::new(
+            x,
+            constraints.min_y + (PLAYER_Y_DELTA * constraints.scale),
+            10.,
+        ))
+        .with_scale(Vec3::splat(SCALE * constraints.scale));
 
         (
             Player {
                 velocity: 0.0,
                 direction: Direction::default(),
             },
-            AnimatedSprite::new(
-                asset_server.load("character_spritesheet.png"),
-                animation_layout_handle,
-                &animation_layout,
-                "idle",
-                24,
-            ),
-            Transform::from_translation(Vec3::new(
-                0.,
-                constraints.min_y + (PLAYER_Y_DELTA * constraints.scale),
-                10.,
-            ))
-            .with_scale(Vec3::splat(SCALE * constraints.scale)),
-            // Uncomment to visualize collision rectangles
+            AnimatedSprite::new(spritesheet.0.clone(), "idle"),
+            AnimationController::new("idle")
+                .with_transition("idle", "walk", [Condition::Above("speed", 0.0)])
+                .with_transition("walk", "idle", [Condition::Below("speed", f32::EPSILON)]),
+            Crossfade::new(CROSSFADE_DURATION),
+            // The walk cycle is drawn for VELOCITY_X
+            PlaybackSpeedBinding::new("walk", |player: &Player| player.velocity.abs() / VELOCITY_X),
+            transform,
+            PhysicalTransform(transform),
+            // Uncomment to visualize the collider's bounds
             // children![(
             //     Sprite::from_color(
             //         Color::srgba(1.0, 1.0, 1.0, 0.3),
@@ -89,36 +124,40 @@ impl Player {
 fn handle_input(
     keys: Res<ButtonInput<KeyCode>>,
     mut player: Query<&mut Player>,
-    constraints: Res<ScreenConstraints>,
+    preset: Res<Preset>,
 ) {
     let Ok(mut player) = player.single_mut() else {
         return;
     };
 
-    let scaled_velocity = VELOCITY_X * constraints.scale;
+    let max_velocity = VELOCITY_X * preset.tuning().player_speed;
     let mut velocity = 0.0;
 
     if keys.pressed(KeyCode::ArrowLeft) || keys.pressed(KeyCode::KeyA) {
-        velocity -= scaled_velocity;
+        velocity -= max_velocity;
     }
 
     if keys.pressed(KeyCode::ArrowRight) || keys.pressed(KeyCode::KeyD) {
-        velocity += scaled_velocity;
+        velocity += max_velocity;
     }
 
-    player.velocity = velocity.clamp(-scaled_velocity, scaled_velocity);
+    player.velocity = velocity.clamp(-max_velocity, max_velocity);
 }
 
 fn movement(
-    time: ResMut<Time>,
-    mut query: Query<(&mut Transform, &mut Player, &mut AnimatedSprite)>,
+    time: Res<Time>,
+    mut query: Query<(
+        &mut PhysicalTransform,
+        &mut Player,
+        &mut AnimationController,
+    )>,
     constraints: Res<ScreenConstraints>,
 ) {
-    let Ok((mut transform, mut player, mut animation)) = query.single_mut() else {
+    let Ok((mut transform, mut player, mut controller)) = query.single_mut() else {
         return;
     };
 
-    let translation_x = player.velocity * time.delta_secs();
+    let translation_x = player.velocity * constraints.scale * time.delta_secs();
     transform.translation.x =
         (transform.translation.x + translation_x).clamp(constraints.min_x, constraints.max_x);
 
@@ -128,11 +167,7 @@ fn movement(
         player.direction = Direction::Right;
     }
 
-    if translation_x == 0.0 {
-        animation.animation_name = "idle".to_string();
-    } else {
-        animation.animation_name = "walk".to_string();
-    }
+    controller.set_float("speed", translation_x.abs());
 }
 
 fn flip_sprite(mut query: Query<(&mut Sprite, &Player)>) {
@@ -145,10 +180,17 @@ fn flip_sprite(mut query: Query<(&mut Sprite, &Player)>) {
 
 fn handle_resize(
     constraints: Res<ScreenConstraints>,
-    mut player: Query<&mut Transform, With<Player>>,
+    mut player: Query<
+        (
+            &mut Transform,
+            &mut PhysicalTransform,
+            &mut PreviousPhysicalTransform,
+        ),
+        With<Player>,
+    >,
     mut previous_scale: Local<Option<f32>>,
 ) {
-    let Ok(mut player) = player.single_mut() else {
+    let Ok((mut transform, mut physical, mut previous)) = player.single_mut() else {
         return;
     };
 
@@ -157,8 +199,9 @@ fn handle_resize(
         None => constraints.scale,
     };
 
-    player.scale = Vec3::splat(SCALE * constraints.scale);
-    player.translation.x *= scale_ratio;
-    player.translation.y = constraints.min_y + (PLAYER_Y_DELTA * constraints.scale);
+    transform.scale = Vec3::splat(SCALE * constraints.scale);
+    physical.translation.x *= scale_ratio;
+    physical.translation.y = constraints.min_y + (PLAYER_Y_DELTA * constraints.scale);
+    previous.reset();
     *previous_scale = Some(constraints.scale);
 }
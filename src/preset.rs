@@ -0,0 +1,147 @@
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+/// Step the menu adjusts custom tuning by
+pub(crate) const TUNING_STEP: f32 = 0.1;
+
+#[derive(Serialize, Deserialize, Default, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[serde(rename_all = "snake_case")]
+pub(crate) enum DifficultyPreset {
+    Easy,
+    #[default]
+    Normal,
+    Hard,
+    Custom,
+}
+
+impl DifficultyPreset {
+    pub(crate) const ALL: [DifficultyPreset; 4] = [
+        DifficultyPreset::Easy,
+        DifficultyPreset::Normal,
+        DifficultyPreset::Hard,
+        DifficultyPreset::Custom,
+    ];
+
+    pub(crate) fn name(self) -> &'static str {
+        match self {
+            DifficultyPreset::Easy => "Easy",
+            DifficultyPreset::Normal => "Normal",
+            DifficultyPreset::Hard => "Hard",
+            DifficultyPreset::Custom => "Custom",
+        }
+    }
+}
+
+/// Multipliers applied on top of the game's own tuning, 1.0 leaves it as is.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+pub(crate) struct Tuning {
+    pub(crate) player_speed: f32,
+    pub(crate) gravity: f32,
+    pub(crate) spawn_rate: f32,
+    /// Size of the player's hitbox, lower is more lenient
+    pub(crate) hitbox: f32,
+}
+
+impl Default for Tuning {
+    fn default() -> Self {
+        Tuning::NORMAL
+    }
+}
+
+impl Tuning {
+    const EASY: Tuning = Tuning {
+        player_speed: 1.1,
+        gravity: 0.8,
+        spawn_rate: 0.75,
+        hitbox: 0.8,
+    };
+    const NORMAL: Tuning = Tuning {
+        player_speed: 1.0,
+        gravity: 1.0,
+        spawn_rate: 1.0,
+        hitbox: 1.0,
+    };
+    const HARD: Tuning = Tuning {
+        player_speed: 1.0,
+        gravity: 1.25,
+        spawn_rate: 1.3,
+        hitbox: 1.1,
+    };
+
+    pub(crate) fn get(&self, field: TuningField) -> f32 {
+        match field {
+            TuningField::PlayerSpeed => self.player_speed,
+            TuningField::Gravity => self.gravity,
+            TuningField::SpawnRate => self.spawn_rate,
+            TuningField::Hitbox => self.hitbox,
+        }
+    }
+
+    /// Changes `field` by `delta`, kept within its range.
+    pub(crate) fn adjust(&mut self, field: TuningField, delta: f32) {
+        let value = match field {
+            TuningField::PlayerSpeed => &mut self.player_speed,
+            TuningField::Gravity => &mut self.gravity,
+            TuningField::SpawnRate => &mut self.spawn_rate,
+            TuningField::Hitbox => &mut self.hitbox,
+        };
+
+        let (min, max) = field.range();
+        // Rounded so repeated steps don't drift
+        *value = ((*value + delta) * 100.0)
+            .round()
+            .clamp(min * 100.0, max * 100.0)
+            / 100.0;
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum TuningField {
+    PlayerSpeed,
+    Gravity,
+    SpawnRate,
+    Hitbox,
+}
+
+impl TuningField {
+    pub(crate) const ALL: [TuningField; 4] = [
+        TuningField::PlayerSpeed,
+        TuningField::Gravity,
+        TuningField::SpawnRate,
+        TuningField::Hitbox,
+    ];
+
+    pub(crate) fn name(self) -> &'static str {
+        match self {
+            TuningField::PlayerSpeed => "Player speed",
+            TuningField::Gravity => "Gravity",
+            TuningField::SpawnRate => "Spawn rate",
+            TuningField::Hitbox => "Hitbox",
+        }
+    }
+
+    fn range(self) -> (f32, f32) {
+        match self {
+            TuningField::Hitbox => (0.5, 1.5),
+            _ => (0.5, 2.0),
+        }
+    }
+}
+
+/// The difficulty preset chosen in the menu, along with the player's custom tuning.
+#[derive(Resource, Serialize, Deserialize, Default, Debug, Clone)]
+pub(crate) struct Preset {
+    pub(crate) selected: DifficultyPreset,
+    pub(crate) custom: Tuning,
+}
+
+impl Preset {
+    pub(crate) fn tuning(&self) -> Tuning {
+        match self.selected {
+            DifficultyPreset::Easy => Tuning::EASY,
+            DifficultyPreset::Normal => Tuning::NORMAL,
+            DifficultyPreset::Hard => Tuning::HARD,
+            DifficultyPreset::Custom => self.custom,
+        }
+    }
+}
@@ -0,0 +1,183 @@
+use bevy::{
+    platform::collections::{HashMap, HashSet},
+    prelude::*,
+};
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+use crate::preset::{DifficultyPreset, Preset};
+
+/// Scores kept per level and preset
+const HIGHSCORE_COUNT: usize = 5;
+
+/// Loads the save when the app starts and writes it back whenever it changes. On the web the
+/// save only lasts as long as the page.
+pub(crate) struct SavePlugin;
+
+impl Plugin for SavePlugin {
+    fn build(&self, app: &mut App) {
+        let save = match storage::load() {
+            Ok(save) => save.unwrap_or_default(),
+            Err(e) => {
+                warn!("Could not load save, starting over: {e}");
+                SaveFile::default()
+            }
+        };
+
+        app.insert_resource(save.preset)
+            .insert_resource(save.highscores)
+            .insert_resource(save.progress)
+            .add_systems(
+                Update,
+                write_save.run_if(
+                    resource_changed::<Preset>
+                        .or(resource_changed::<Highscores>)
+                        .or(resource_changed::<Progress>),
+                ),
+            );
+    }
+}
+
+#[derive(Error, Debug)]
+pub(crate) enum SaveError {
+    #[error("could not access save: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("could not parse save: {0}")]
+    Json(#[from] serde_json::Error),
+}
+
+#[derive(Serialize, Deserialize, Default)]
+struct SaveFile {
+    #[serde(default)]
+    preset: Preset,
+    #[serde(default)]
+    highscores: Highscores,
+    #[serde(default)]
+    progress: Progress,
+}
+
+/// Best scores in whole seconds, highest first, kept separately for each level, by its
+/// [`level_id`](crate::level::level_id), and preset.
+#[derive(Resource, Serialize, Deserialize, Default, Debug, Clone)]
+pub(crate) struct Highscores(HashMap<String, HashMap<DifficultyPreset, Vec<u32>>>);
+
+impl Highscores {
+    pub(crate) fn table(&self, level_id: &str, preset: DifficultyPreset) -> &[u32] {
+        self.0
+            .get(level_id)
+            .and_then(|tables| tables.get(&preset))
+            .map_or(&[], Vec::as_slice)
+    }
+
+    /// Adds `score` to the level's table for the preset, returning its rank if it made it in.
+    pub(crate) fn record(
+        &mut self,
+        level_id: String,
+        preset: DifficultyPreset,
+        score: u32,
+    ) -> Option<usize> {
+        let table = self
+            .0
+            .entry(level_id)
+            .or_default()
+            .entry(preset)
+            .or_default();
+        let rank = table.partition_point(|&s| s >= score);
+        if rank >= HIGHSCORE_COUNT {
+            return None;
+        }
+
+        table.insert(rank, score);
+        table.truncate(HIGHSCORE_COUNT);
+        Some(rank)
+    }
+}
+
+/// Levels the player has cleared, by their [`level_id`](crate::level::level_id).
+#[derive(Resource, Serialize, Deserialize, Default, Debug, Clone)]
+pub(crate) struct Progress {
+    cleared: HashSet<String>,
+}
+
+impl Progress {
+    pub(crate) fn is_cleared(&self, level_id: &str) -> bool {
+        self.cleared.contains(level_id)
+    }
+
+    pub(crate) fn mark_cleared(&mut self, level_id: String) {
+        self.cleared.insert(level_id);
+    }
+}
+
+fn write_save(preset: Res<Preset>, highscores: Res<Highscores>, progress: Res<Progress>) {
+    let save = SaveFile {
+        preset: preset.clone(),
+        highscores: highscores.clone(),
+        progress: progress.clone(),
+    };
+
+    if let Err(e) = storage::store(&save) {
+        warn!("Could not write save: {e}");
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+mod storage {
+    use std::{fs, io::ErrorKind, path::PathBuf};
+
+    use bevy::log::warn;
+
+    use super::{SaveError, SaveFile};
+
+    const SAVE_FILE_NAME: &str = "save.json";
+
+    /// `$XDG_DATA_HOME/downfall`, falling back to `%APPDATA%\downfall` on Windows and
+    /// `~/.local/share/downfall` elsewhere
+    fn dir() -> Option<PathBuf> {
+        let data_home = std::env::var_os("XDG_DATA_HOME")
+            .map(PathBuf::from)
+            .filter(|path| path.is_absolute())
+            .or_else(|| std::env::var_os("APPDATA").map(PathBuf::from))
+            .or_else(|| {
+                std::env::var_os("HOME").map(|home| PathBuf::from(home).join(".local/share"))
+            })?;
+
+        Some(data_home.join("downfall"))
+    }
+
+    pub(super) fn load() -> Result<Option<SaveFile>, SaveError> {
+        let Some(dir) = dir() else {
+            warn!("No place to keep the save, progress will be lost on exit");
+            return Ok(None);
+        };
+
+        match fs::read(dir.join(SAVE_FILE_NAME)) {
+            Ok(bytes) => Ok(Some(serde_json::from_slice(&bytes)?)),
+            Err(e) if e.kind() == ErrorKind::NotFound => Ok(None),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    pub(super) fn store(save: &SaveFile) -> Result<(), SaveError> {
+        let Some(dir) = dir() else {
+            return Ok(());
+        };
+
+        fs::create_dir_all(&dir)?;
+        fs::write(dir.join(SAVE_FILE_NAME), serde_json::to_vec_pretty(save)?)?;
+        Ok(())
+    }
+}
+
+#[cfg(target_arch = "wasm32")]
+mod storage {
+    use super::{SaveError, SaveFile};
+
+    pub(super) fn load() -> Result<Option<SaveFile>, SaveError> {
+        Ok(None)
+    }
+
+    pub(super) fn store(_save: &SaveFile) -> Result<(), SaveError> {
+        Ok(())
+    }
+}
@@ -0,0 +1,270 @@
+use std::time::Duration;
+
+use bevy::{ecs::system::SystemParam, prelude::*};
+
+use crate::{
+    debris::{self, Debris, DebrisData, DebrisSpawn},
+    difficulty::PacedTime,
+    game::{GameState, InGameState, ScreenConstraints},
+};
+
+const SHADOW_COLOR: Color = Color::srgba(0.0, 0.0, 0.0, 0.35);
+const MARKER_COLOR: Color = Color::srgb(0.9, 0.2, 0.1);
+/// Shadow height as a fraction of its width
+const SHADOW_ASPECT: f32 = 0.25;
+const MARKER_SIZE: f32 = 30.0;
+const MARKER_PULSES_PER_SEC: f32 = 4.0;
+
+pub(crate) struct TelegraphPlugin;
+
+impl Plugin for TelegraphPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<TelegraphSettings>()
+            .init_resource::<TelegraphAssets>()
+            .add_message::<SpawnDebris>()
+            .add_systems(
+                FixedUpdate,
+                (
+                    start_telegraphs.in_set(TelegraphSystems),
+                    release_telegraphed_debris,
+                )
+                    .run_if(in_state(InGameState::Running)),
+            )
+            .add_systems(
+                Update,
+                animate_telegraphs.run_if(in_state(InGameState::Running)),
+            )
+            .add_systems(OnExit(GameState::InGame), despawn_telegraphs);
+    }
+}
+
+/// Starts telegraphs for the step's [`SpawnDebris`] requests. Systems sending them run before,
+/// so [`IncomingDebris`] counts their debris from the next step on.
+#[derive(SystemSet, Debug, Clone, PartialEq, Eq, Hash)]
+pub(crate) struct TelegraphSystems;
+
+/// How debris is announced before it appears.
+#[derive(Resource, Clone, Debug)]
+pub(crate) struct TelegraphSettings {
+    /// Grows a shadow on the ground where the debris will land
+    pub(crate) shadow: bool,
+    /// Shows a marker at the top of the screen above where the debris appears
+    pub(crate) marker: bool,
+}
+
+impl Default for TelegraphSettings {
+    fn default() -> Self {
+        TelegraphSettings {
+            shadow: true,
+            marker: true,
+        }
+    }
+}
+
+impl TelegraphSettings {
+    pub(crate) fn is_enabled(&self) -> bool {
+        self.shadow || self.marker
+    }
+}
+
+#[derive(Resource)]
+struct TelegraphAssets {
+    shadow_mesh: Handle<Mesh>,
+    shadow_material: Handle<ColorMaterial>,
+    marker_mesh: Handle<Mesh>,
+    marker_material: Handle<ColorMaterial>,
+}
+
+impl FromWorld for TelegraphAssets {
+    fn from_world(world: &mut World) -> Self {
+        let mut meshes = world.resource_mut::<Assets<Mesh>>();
+        let shadow_mesh = meshes.add(Ellipse::new(0.5, 0.5 * SHADOW_ASPECT));
+        let marker_mesh = meshes.add(Triangle2d::new(
+            Vec2::new(-0.5, 0.5),
+            Vec2::new(0.0, -0.5),
+            Vec2::new(0.5, 0.5),
+        ));
+
+        let mut materials = world.resource_mut::<Assets<ColorMaterial>>();
+        TelegraphAssets {
+            shadow_mesh,
+            shadow_material: materials.add(SHADOW_COLOR),
+            marker_mesh,
+            marker_material: materials.add(MARKER_COLOR),
+        }
+    }
+}
+
+/// Debris waiting for its telegraph to finish before it appears.
+#[derive(Component)]
+struct Telegraph {
+    spawn: DebrisSpawn,
+    timer: Timer,
+    /// Full width of the shadow, unscaled. It's the width of the debris' collider rather than
+    /// its sprite, so the shadow shows exactly where the debris can hit.
+    width: f32,
+}
+
+/// Debris that is falling or telegraphed to appear, for spawn limits.
+#[derive(SystemParam)]
+pub(crate) struct IncomingDebris<'w, 's> {
+    debris: Query<'w, 's, &'static Debris>,
+    telegraphs: Query<'w, 's, &'static Telegraph>,
+}
+
+impl IncomingDebris<'_, '_> {
+    /// How many of each of the `definitions` there are, by index.
+    pub(crate) fn counts(&self, definitions: usize) -> Vec<usize> {
+        let mut counts = vec![0; definitions];
+        let incoming = self
+            .debris
+            .iter()
+            .map(|debris| debris.definition_idx)
+            .chain(
+                self.telegraphs
+                    .iter()
+                    .map(|telegraph| telegraph.spawn.definition_idx),
+            );
+
+        for idx in incoming {
+            if let Some(count) = counts.get_mut(idx) {
+                *count += 1;
+            }
+        }
+
+        counts
+    }
+}
+
+#[derive(Component)]
+struct Shadow;
+
+#[derive(Component)]
+struct Marker;
+
+/// Asks for debris to be spawned after the [`Difficulty`](crate::difficulty::Difficulty)'s
+/// telegraph time, or right away if telegraphs are turned off.
+#[derive(Message)]
+pub(crate) struct SpawnDebris(pub(crate) DebrisSpawn);
+
+fn start_telegraphs(
+    mut commands: Commands,
+    mut requests: MessageReader<SpawnDebris>,
+    settings: Res<TelegraphSettings>,
+    assets: Res<TelegraphAssets>,
+    data: Option<Res<DebrisData>>,
+    constraints: Res<ScreenConstraints>,
+    time: PacedTime,
+) {
+    let Some(data) = data else {
+        requests.clear();
+        return;
+    };
+
+    // Without a telegraph the debris is released on the next step
+    let lead_time = if settings.is_enabled() {
+        time.telegraph_lead()
+    } else {
+        Duration::ZERO
+    };
+    let shown = !lead_time.is_zero();
+
+    for SpawnDebris(spawn) in requests.read() {
+        let (Some(definition), Some(landing_x)) = (
+            data.definitions.get(spawn.definition_idx),
+            spawn.landing_x(&data, *constraints, &time),
+        ) else {
+            continue;
+        };
+
+        let mut telegraph = commands.spawn((
+            Telegraph {
+                spawn: *spawn,
+                timer: Timer::new(lead_time, TimerMode::Once),
+                width: definition.collider.size().x,
+            },
+            Transform::default(),
+            Visibility::default(),
+        ));
+
+        if settings.shadow && shown {
+            telegraph.with_child((
+                Shadow,
+                Mesh2d(assets.shadow_mesh.clone()),
+                MeshMaterial2d(assets.shadow_material.clone()),
+                Transform::from_xyz(landing_x, debris::ground_y(*constraints), 0.5)
+                    .with_scale(Vec3::ZERO),
+            ));
+        }
+
+        if settings.marker && shown {
+            telegraph.with_child((
+                Marker,
+                Mesh2d(assets.marker_mesh.clone()),
+                MeshMaterial2d(assets.marker_material.clone()),
+                Transform::from_xyz(
+                    spawn.x,
+                    constraints.max_y - MARKER_SIZE * constraints.scale,
+                    20.0,
+                )
+                .with_scale(Vec3::splat(MARKER_SIZE * constraints.scale)),
+            ));
+        }
+    }
+}
+
+fn release_telegraphed_debris(
+    mut commands: Commands,
+    mut telegraphs: Query<(Entity, &mut Telegraph)>,
+    data: Option<Res<DebrisData>>,
+    asset_server: Res<AssetServer>,
+    constraints: Res<ScreenConstraints>,
+    time: Res<Time>,
+) {
+    for (entity, mut telegraph) in telegraphs.iter_mut() {
+        if !telegraph.timer.tick(time.delta()).is_finished() {
+            continue;
+        }
+
+        commands.entity(entity).despawn();
+        if let Some(debris) = data
+            .as_ref()
+            .and_then(|data| telegraph.spawn.bundle(data, &asset_server, *constraints))
+        {
+            commands.spawn(debris);
+        }
+    }
+}
+
+fn animate_telegraphs(
+    telegraphs: Query<(&Telegraph, &Children)>,
+    mut shadows: Query<&mut Transform, (With<Shadow>, Without<Marker>)>,
+    mut markers: Query<&mut Transform, (With<Marker>, Without<Shadow>)>,
+    constraints: Res<ScreenConstraints>,
+) {
+    for (telegraph, children) in telegraphs.iter() {
+        let progress = telegraph.timer.fraction();
+
+        for child in children.iter() {
+            if let Ok(mut transform) = shadows.get_mut(child) {
+                transform.scale = Vec3::splat(telegraph.width * constraints.scale * progress);
+            }
+
+            if let Ok(mut transform) = markers.get_mut(child) {
+                let pulse = (progress
+                    * telegraph.timer.duration().as_secs_f32()
+                    * MARKER_PULSES_PER_SEC
+                    * std::f32::consts::TAU)
+                    .cos()
+                    * 0.15;
+                transform.scale = Vec3::splat(MARKER_SIZE * constraints.scale * (1.0 + pulse));
+            }
+        }
+    }
+}
+
+fn despawn_telegraphs(mut commands: Commands, telegraphs: Query<Entity, With<Telegraph>>) {
+    for entity in telegraphs.iter() {
+        commands.entity(entity).despawn();
+    }
+}
@@ -0,0 +1,568 @@
+use bevy::{
+    asset::{AssetLoader, LoadContext, io::Reader},
+    platform::collections::HashMap,
+    prelude::*,
+    time::Stopwatch,
+};
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+use crate::{
+    debris::{DebrisData, DebrisSpawn},
+    difficulty::PacedTime,
+    game::{GameState, InGameState, ScreenConstraints},
+    level::{LevelDefinition, LoadedLevel},
+    telegraph::{IncomingDebris, SpawnDebris, TelegraphSystems},
+};
+
+pub(crate) struct WavesPlugin;
+
+impl Plugin for WavesPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_asset::<WaveData>()
+            .init_asset_loader::<WaveDataLoader>()
+            .add_systems(OnEnter(GameState::InGame), start_waves)
+            .add_systems(OnExit(GameState::InGame), stop_waves)
+            .add_systems(
+                FixedUpdate,
+                direct_waves
+                    .before(TelegraphSystems)
+                    .run_if(in_state(InGameState::Running))
+                    .run_if(resource_exists::<LevelDirector>.and(resource_exists::<DebrisData>)),
+            );
+    }
+}
+
+#[derive(Error, Debug)]
+pub(crate) enum WaveDataLoaderError {
+    #[error("could not read waves: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("could not parse waves: {0}")]
+    Json(#[from] serde_json::Error),
+    #[error("there are no waves")]
+    Empty,
+    #[error("wave {wave} uses unknown pattern {pattern}")]
+    UnknownPattern { wave: usize, pattern: String },
+    #[error("waves loop from {0}, past the last wave")]
+    LoopOutOfRange(usize),
+}
+
+#[derive(Default)]
+struct WaveDataLoader;
+
+impl AssetLoader for WaveDataLoader {
+    type Asset = WaveData;
+    type Settings = ();
+    type Error = WaveDataLoaderError;
+
+    async fn load(
+        &self,
+        reader: &mut dyn Reader,
+        _settings: &(),
+        _load_context: &mut LoadContext<'_>,
+    ) -> Result<Self::Asset, Self::Error> {
+        let mut bytes = Vec::new();
+        reader.read_to_end(&mut bytes).await?;
+        let data: WaveData = serde_json::from_slice(&bytes)?;
+
+        if data.waves.is_empty() {
+            return Err(WaveDataLoaderError::Empty);
+        }
+
+        if let Some((wave, unknown)) = data
+            .waves
+            .iter()
+            .enumerate()
+            .find(|(_, wave)| !data.patterns.contains_key(&wave.pattern))
+        {
+            return Err(WaveDataLoaderError::UnknownPattern {
+                wave,
+                pattern: unknown.pattern.clone(),
+            });
+        }
+
+        if data.loop_from >= data.waves.len() {
+            return Err(WaveDataLoaderError::LoopOutOfRange(data.loop_from));
+        }
+
+        Ok(data)
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["json"]
+    }
+}
+
+/// Named spawn patterns sequenced into waves.
+#[derive(Asset, TypePath, Serialize, Deserialize, Clone)]
+pub(crate) struct WaveData {
+    pub(crate) patterns: HashMap<String, Pattern>,
+    pub(crate) waves: Vec<Wave>,
+    /// Wave to continue from after the last one
+    #[serde(default)]
+    pub(crate) loop_from: usize,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub(crate) struct Wave {
+    pub(crate) pattern: String,
+    /// Times the pattern plays in a row
+    #[serde(default = "default_repeat")]
+    pub(crate) repeat: u32,
+    /// Pause after each time the pattern plays
+    #[serde(default)]
+    pub(crate) interval_secs: f32,
+}
+
+fn default_repeat() -> u32 {
+    1
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub(crate) struct Pattern {
+    #[serde(flatten)]
+    pub(crate) shape: PatternShape,
+    /// Debris to use, picked by weight for every spawn if absent, unknown or not allowed yet
+    #[serde(default)]
+    pub(crate) debris: Option<String>,
+}
+
+/// Where and when a pattern drops debris. Positions are fractions of the screen width.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub(crate) enum PatternShape {
+    /// One debris anywhere
+    Single,
+    /// A row of `columns` at once, leaving a gap of `gap` neighboring columns
+    Wall { columns: u32, gap: u32 },
+    /// `steps` debris crossing the screen one after another
+    Staircase { steps: u32, interval_secs: f32 },
+    /// `count` debris alternating sides of a random center, `width` apart
+    Zigzag {
+        count: u32,
+        width: f32,
+        interval_secs: f32,
+    },
+    /// `count` debris at once, within `spread` of a random center
+    Burst { count: u32, spread: f32 },
+    /// `count` debris at random places and times over `duration_secs`
+    Rain { count: u32, duration_secs: f32 },
+}
+
+/// A spawn a pattern schedules, `delay_secs` after the pattern starts.
+struct ScheduledSpawn {
+    delay_secs: f32,
+    x: f32,
+}
+
+impl PatternShape {
+    fn schedule(&self) -> Vec<ScheduledSpawn> {
+        let mut rng = rand::rng();
+        let at = |delay_secs: f32, x: f32| ScheduledSpawn {
+            delay_secs,
+            x: x.clamp(0.0, 1.0),
+        };
+
+        match *self {
+            PatternShape::Single => vec![at(0.0, rng.random())],
+            PatternShape::Wall { columns, gap } => {
+                let gap = gap.min(columns);
+                let gap_start = rng.random_range(0..=columns - gap);
+                (0..columns)
+                    .filter(|column| !(gap_start..gap_start + gap).contains(column))
+                    .map(|column| at(0.0, (column as f32 + 0.5) / columns as f32))
+                    .collect()
+            }
+            PatternShape::Staircase {
+                steps,
+                interval_secs,
+            } => {
+                let left_to_right = rng.random_bool(0.5);
+                (0..steps)
+                    .map(|step| {
+                        let x = (step as f32 + 0.5) / steps as f32;
+                        at(
+                            step as f32 * interval_secs,
+                            if left_to_right { x } else { 1.0 - x },
+                        )
+                    })
+                    .collect()
+            }
+            PatternShape::Zigzag {
+                count,
+                width,
+                interval_secs,
+            } => {
+                let half = (width / 2.0).clamp(0.0, 0.5);
+                let center = rng.random_range(half..=1.0 - half);
+                (0..count)
+                    .map(|i| {
+                        let side = if i % 2 == 0 { -half } else { half };
+                        at(i as f32 * interval_secs, center + side)
+                    })
+                    .collect()
+            }
+            PatternShape::Burst { count, spread } => {
+                let center: f32 = rng.random();
+                (0..count)
+                    .map(|_| at(0.0, center + rng.random_range(-1.0..=1.0) * spread))
+                    .collect()
+            }
+            PatternShape::Rain {
+                count,
+                duration_secs,
+            } => (0..count)
+                .map(|_| at(rng.random::<f32>() * duration_secs, rng.random()))
+                .collect(),
+        }
+    }
+}
+
+/// Plays a level's waves in order, spawning the debris of their patterns.
+#[derive(Resource)]
+pub(crate) struct LevelDirector {
+    waves: Handle<WaveData>,
+    /// Debris picked at random, any if empty
+    debris_pool: Vec<String>,
+    wave: usize,
+    /// Times the current wave's pattern has played
+    played: u32,
+    /// Time since the current pattern started
+    pattern_time: Stopwatch,
+    /// Time until the next pattern starts
+    cooldown_secs: f32,
+    pending: Vec<ScheduledSpawn>,
+    /// Definition of the debris each pattern names, resolved when the run starts. Patterns
+    /// naming unknown debris are left out and pick theirs like patterns that name none.
+    pattern_debris: HashMap<String, usize>,
+    /// The current pattern's named debris
+    debris: Option<usize>,
+    /// Time since the run started, for debris that only appears later
+    run_time: Stopwatch,
+}
+
+impl LevelDirector {
+    pub(crate) fn new(level: &LevelDefinition, waves: &WaveData, debris_data: &DebrisData) -> Self {
+        let mut pattern_debris = HashMap::default();
+        for (name, pattern) in &waves.patterns {
+            let Some(debris) = &pattern.debris else {
+                continue;
+            };
+
+            match debris_data.find(debris) {
+                Some(idx) => {
+                    pattern_debris.insert(name.clone(), idx);
+                }
+                None => warn!("Pattern {name} uses unknown debris {debris}, picking at random"),
+            }
+        }
+
+        LevelDirector {
+            waves: level.waves.clone(),
+            debris_pool: level.debris_pool.clone(),
+            wave: 0,
+            played: 0,
+            pattern_time: Stopwatch::new(),
+            cooldown_secs: 0.0,
+            pending: Vec::new(),
+            pattern_debris,
+            debris: None,
+            run_time: Stopwatch::new(),
+        }
+    }
+}
+
+fn start_waves(
+    mut commands: Commands,
+    level: LoadedLevel,
+    waves: Res<Assets<WaveData>>,
+    debris_data: Option<Res<DebrisData>>,
+) {
+    let Some(level) = level.get() else {
+        return;
+    };
+
+    let (Some(waves), Some(debris_data)) = (waves.get(&level.waves), debris_data) else {
+        warn!("Started a game before its waves and debris loaded");
+        return;
+    };
+
+    commands.insert_resource(LevelDirector::new(level, waves, &debris_data));
+}
+
+fn stop_waves(mut commands: Commands) {
+    commands.remove_resource::<LevelDirector>();
+}
+
+fn direct_waves(
+    mut director: ResMut<LevelDirector>,
+    mut spawns: MessageWriter<SpawnDebris>,
+    waves: Res<Assets<WaveData>>,
+    debris_data: Res<DebrisData>,
+    incoming: IncomingDebris,
+    constraints: Res<ScreenConstraints>,
+    time: PacedTime,
+) {
+    director.run_time.tick(time.delta());
+    director.pattern_time.tick(time.spawn_delta());
+    director.cooldown_secs -= time.spawn_delta().as_secs_f32();
+
+    if director.cooldown_secs <= 0.0
+        && director.pending.is_empty()
+        && let Some(waves) = waves.get(&director.waves)
+    {
+        start_next_pattern(&mut director, waves);
+    }
+
+    let elapsed = director.pattern_time.elapsed_secs();
+    if !director
+        .pending
+        .iter()
+        .any(|spawn| spawn.delay_secs <= elapsed)
+    {
+        return;
+    }
+
+    let mut counts = incoming.counts(debris_data.definitions.len());
+    let mut total: usize = counts.iter().sum();
+
+    let run_secs = director.run_time.elapsed_secs();
+    let (due, pending): (Vec<_>, Vec<_>) = std::mem::take(&mut director.pending)
+        .into_iter()
+        .partition(|spawn| spawn.delay_secs <= elapsed);
+    director.pending = pending;
+
+    for spawn in due {
+        // Crowded out spawns wait for room
+        if total >= time.max_debris() {
+            director.pending.push(spawn);
+            continue;
+        }
+
+        // Named debris the level doesn't allow right now is replaced by whatever it does
+        let definition_idx = match director.debris {
+            Some(idx) if debris_data.allows(idx, run_secs, &counts, &director.debris_pool) => {
+                Some(idx)
+            }
+            _ => debris_data.pick(run_secs, &counts, &director.debris_pool),
+        };
+
+        let x = constraints.min_x + spawn.x * (constraints.max_x - constraints.min_x);
+        if let Some(spawn) = definition_idx.and_then(|idx| DebrisSpawn::new(&debris_data, idx, x)) {
+            if let Some(count) = counts.get_mut(spawn.definition_idx) {
+                *count += 1;
+            }
+            total += 1;
+            spawns.write(SpawnDebris(spawn));
+        }
+    }
+}
+
+fn start_next_pattern(director: &mut LevelDirector, waves: &WaveData) {
+    // Waves can shrink when hot reloaded
+    if director.wave >= waves.waves.len() {
+        director.wave = waves.loop_from.min(waves.waves.len() - 1);
+        director.played = 0;
+    }
+
+    let wave = &waves.waves[director.wave];
+    let Some(pattern) = waves.patterns.get(&wave.pattern) else {
+        return;
+    };
+
+    director.pending = pattern.shape.schedule();
+    director.debris = director.pattern_debris.get(&wave.pattern).copied();
+    director.pattern_time.reset();
+
+    let duration = director
+        .pending
+        .iter()
+        .map(|spawn| spawn.delay_secs)
+        .fold(0.0, f32::max);
+    director.cooldown_secs = duration + wave.interval_secs;
+
+    director.played += 1;
+    if director.played >= wave.repeat {
+        director.played = 0;
+        director.wave += 1;
+        if director.wave >= waves.waves.len() {
+            director.wave = waves.loop_from;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+    use crate::{difficulty::Difficulty, preset::Preset};
+
+    fn xs(spawns: &[ScheduledSpawn]) -> Vec<f32> {
+        spawns.iter().map(|spawn| spawn.x).collect()
+    }
+
+    fn delays(spawns: &[ScheduledSpawn]) -> Vec<f32> {
+        spawns.iter().map(|spawn| spawn.delay_secs).collect()
+    }
+
+    fn assert_close(actual: &[f32], expected: &[f32]) {
+        assert!(
+            actual.len() == expected.len()
+                && actual
+                    .iter()
+                    .zip(expected)
+                    .all(|(a, e)| (a - e).abs() < 1e-5),
+            "{actual:?} != {expected:?}"
+        );
+    }
+
+    #[test]
+    fn staircase_crosses_the_screen_one_step_at_a_time() {
+        let shape = PatternShape::Staircase {
+            steps: 4,
+            interval_secs: 0.5,
+        };
+
+        for _ in 0..20 {
+            let spawns = shape.schedule();
+            let mut x = xs(&spawns);
+            if x[0] > x[1] {
+                x.reverse();
+            }
+            assert_close(&x, &[0.125, 0.375, 0.625, 0.875]);
+            assert_close(&delays(&spawns), &[0.0, 0.5, 1.0, 1.5]);
+        }
+    }
+
+    #[test]
+    fn zigzag_alternates_around_its_center() {
+        let shape = PatternShape::Zigzag {
+            count: 5,
+            width: 0.4,
+            interval_secs: 0.25,
+        };
+
+        for _ in 0..20 {
+            let spawns = shape.schedule();
+            let x = xs(&spawns);
+            let center = x[0] + 0.2;
+            assert!((0.2..=0.8).contains(&center), "{x:?}");
+            assert_close(
+                &x,
+                &[
+                    center - 0.2,
+                    center + 0.2,
+                    center - 0.2,
+                    center + 0.2,
+                    center - 0.2,
+                ],
+            );
+            assert_close(&delays(&spawns), &[0.0, 0.25, 0.5, 0.75, 1.0]);
+        }
+
+        // Too wide to fit, the zigzag spans the screen instead
+        let wide = PatternShape::Zigzag {
+            count: 3,
+            width: 2.0,
+            interval_secs: 0.0,
+        };
+        assert_close(&xs(&wide.schedule()), &[0.0, 1.0, 0.0]);
+    }
+
+    #[test]
+    fn wall_leaves_one_gap() {
+        let shape = PatternShape::Wall { columns: 5, gap: 2 };
+        let columns = [0.1, 0.3, 0.5, 0.7, 0.9];
+
+        for _ in 0..20 {
+            let spawns = shape.schedule();
+            assert_close(&delays(&spawns), &[0.0; 3]);
+
+            let missing: Vec<usize> = (0..columns.len())
+                .filter(|&i| !spawns.iter().any(|s| (s.x - columns[i]).abs() < 1e-5))
+                .collect();
+            assert_eq!(missing.len(), 2, "{:?}", xs(&spawns));
+            assert_eq!(missing[1], missing[0] + 1, "{:?}", xs(&spawns));
+        }
+
+        let all_gap = PatternShape::Wall { columns: 3, gap: 5 };
+        assert!(all_gap.schedule().is_empty());
+    }
+
+    /// An app directing a single wave of `pattern`, with one kind of debris and the default
+    /// difficulty.
+    fn director_app(pattern: serde_json::Value) -> App {
+        let waves: WaveData = serde_json::from_value(json!({
+            "patterns": { "pattern": pattern },
+            "waves": [{ "pattern": "pattern", "interval_secs": 10.0 }]
+        }))
+        .unwrap();
+        let debris_data: DebrisData = serde_json::from_value(json!({
+            "definitions": [{
+                "name": "brick",
+                "sprite_path": "brick.png",
+                "scale": 1.0,
+                "collider": { "shape": "circle", "radius": 10.0 }
+            }]
+        }))
+        .unwrap();
+
+        let mut app = App::new();
+        app.add_message::<SpawnDebris>()
+            .init_resource::<Time>()
+            .init_resource::<Difficulty>()
+            .init_resource::<Preset>()
+            .init_resource::<ScreenConstraints>()
+            .init_resource::<Assets<WaveData>>()
+            .add_systems(Update, direct_waves);
+
+        let level = LevelDefinition {
+            name: "test".to_string(),
+            background: Handle::default(),
+            music: None,
+            debris_pool: Vec::new(),
+            waves: app
+                .world_mut()
+                .resource_mut::<Assets<WaveData>>()
+                .add(waves.clone()),
+            difficulty: Handle::default(),
+            win: default(),
+            player_x: 0.5,
+            hud_color: Color::WHITE,
+        };
+        app.insert_resource(LevelDirector::new(&level, &waves, &debris_data))
+            .insert_resource(debris_data);
+        app
+    }
+
+    fn step(app: &mut App) -> usize {
+        app.update();
+        app.world_mut()
+            .resource_mut::<Messages<SpawnDebris>>()
+            .drain()
+            .count()
+    }
+
+    #[test]
+    fn crowded_out_wall_pieces_wait_for_room_without_more_delay() {
+        let max_debris = Difficulty::default().max_debris as usize;
+        let mut app = director_app(json!({ "type": "wall", "columns": 12, "gap": 0 }));
+
+        assert_eq!(step(&mut app), max_debris);
+        let director = app.world().resource::<LevelDirector>();
+        assert_eq!(director.pending.len(), 12 - max_debris);
+        assert!(director.pending.iter().all(|spawn| spawn.delay_secs == 0.0));
+
+        // Nothing was actually spawned, so there's room for the rest right away
+        assert_eq!(step(&mut app), 12 - max_debris);
+        assert!(app.world().resource::<LevelDirector>().pending.is_empty());
+    }
+
+    #[test]
+    fn unknown_debris_falls_back_to_picking() {
+        let mut app = director_app(json!({ "type": "single", "debris": "anvil" }));
+        assert_eq!(step(&mut app), 1);
+    }
+}